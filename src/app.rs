@@ -1,7 +1,10 @@
 use {
     crate::{
-        input,
-        keyboard::{self, Key, KeyPos},
+        input::{self, pedal::Pedal, recording::Recording},
+        keyboard::{self, Key, KeyPos, KeySignature, Keyboard, KeyboardSize},
+        naming::Naming,
+        piano,
+        scale::Scale,
         util,
         verovio,
     },
@@ -9,7 +12,9 @@ use {
     gloo_storage::Storage as _,
     iced::{Color, Element, Length, Subscription, Task, Theme, font, widget},
     midly::MidiMessage,
+    notification::Notice,
     serde::{Deserialize, Serialize},
+    std::sync::Arc,
     tap::TapFallible as _,
 };
 
@@ -17,6 +22,8 @@ mod game_active;
 mod game_finished;
 mod loading;
 mod main_menu;
+mod notification;
+mod shortcut;
 
 const TITLE: &str = "Clef Rush";
 const EXPLAIN_UI: bool = false;
@@ -40,6 +47,159 @@ impl OctaveRange {
     }
 }
 
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HintDelay {
+    #[default]
+    #[display("Never")]
+    Never,
+
+    #[display("After {} seconds", _0)]
+    Seconds(u32),
+}
+
+impl HintDelay {
+    pub const ALL: [Self; 4] = [
+        Self::Never,
+        Self::Seconds(5),
+        Self::Seconds(10),
+        Self::Seconds(20),
+    ];
+}
+
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HintAttempts {
+    #[default]
+    #[display("Never")]
+    Never,
+
+    #[display("After {} wrong keys", _0)]
+    Wrong(u8),
+}
+
+impl HintAttempts {
+    pub const ALL: [Self; 4] = [Self::Never, Self::Wrong(1), Self::Wrong(3), Self::Wrong(5)];
+
+    /// Returns `true` if the number of wrong attempts should reveal the next
+    /// hint stage.
+    pub fn should_reveal(&self, wrong_attempts: u32) -> bool {
+        match self {
+            Self::Never | Self::Wrong(0) => false,
+            Self::Wrong(num) => wrong_attempts % *num as u32 == 0,
+        }
+    }
+}
+
+/// When to reveal the solution on the on-screen keyboard. Each trigger reveals
+/// more: first the pitch class of the expected keys, then the keys themselves.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HintConfig {
+    pub delay: HintDelay,
+    pub attempts: HintAttempts,
+}
+
+/// Sounds played by the built-in synthesizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundConfig {
+    /// Plays the notes of the pressed keys.
+    pub notes: bool,
+    /// Plays a click on correct keys and an error sound on wrong ones.
+    pub effects: bool,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            notes: true,
+            effects: true,
+        }
+    }
+}
+
+/// Game action triggered by a pedal, instead of its usual effect on the notes.
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PedalAction {
+    #[default]
+    #[display("No action")]
+    None,
+
+    #[display("Skip challenge")]
+    Skip,
+
+    #[display("Reveal hint")]
+    Hint,
+}
+
+impl PedalAction {
+    pub const ALL: [Self; 3] = [Self::None, Self::Skip, Self::Hint];
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PedalBindings {
+    pub sustain: PedalAction,
+    pub sostenuto: PedalAction,
+    pub soft: PedalAction,
+}
+
+impl PedalBindings {
+    pub fn get(&self, pedal: Pedal) -> PedalAction {
+        match pedal {
+            Pedal::Sustain => self.sustain,
+            Pedal::Sostenuto => self.sostenuto,
+            Pedal::Soft => self.soft,
+        }
+    }
+
+    pub fn set(&mut self, pedal: Pedal, action: PedalAction) {
+        match pedal {
+            Pedal::Sustain => self.sustain = action,
+            Pedal::Sostenuto => self.sostenuto = action,
+            Pedal::Soft => self.soft = action,
+        }
+    }
+
+    /// Returns the pedals bound to game actions.
+    pub fn bound(&self) -> impl Iterator<Item = Pedal> {
+        Pedal::ALL
+            .into_iter()
+            .filter(|pedal| self.get(*pedal) != PedalAction::None)
+    }
+}
+
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeSetting {
+    #[display("Light")]
+    Light,
+
+    #[display("Dark")]
+    Dark,
+
+    #[default]
+    #[display("Follow system")]
+    System,
+}
+
+impl ThemeSetting {
+    pub const ALL: [Self; 3] = [Self::Light, Self::Dark, Self::System];
+
+    pub fn to_theme(&self) -> Theme {
+        match self {
+            Self::Light => Theme::Light,
+            Self::Dark => Theme::Dark,
+            // Detects the system theme with the `auto-detect-theme` feature.
+            Self::System => Theme::default(),
+        }
+    }
+
+    /// Returns the CSS color used to render the staff, matching the text color
+    /// of the theme.
+    pub fn staff_color(&self) -> String {
+        let [r, g, b, _] = self.to_theme().palette().text.into_rgba8();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Clef {
     Treble,
@@ -48,7 +208,30 @@ pub enum Clef {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub input_device: input::Device,
+    /// Input devices used in addition to the on-screen keyboard. Events of all
+    /// enabled devices are merged.
+    #[serde(default)]
+    pub input_devices: Vec<input::Selection>,
+    /// Single input device stored by older versions, migrated when loading.
+    #[serde(default, rename = "input_device", skip_serializing)]
+    legacy_input_device: Option<input::Device>,
+    #[serde(default)]
+    pub keyboard: KeyboardSize,
+    #[serde(default)]
+    pub naming: Naming,
+    /// Mapping of the computer keyboard when used as the input device.
+    #[serde(default)]
+    pub qwerty: input::qwerty::Mapping,
+    #[serde(default)]
+    pub hints: HintConfig,
+    #[serde(default)]
+    pub pedals: PedalBindings,
+    #[serde(default)]
+    pub output: input::output::Config,
+    #[serde(default)]
+    pub sound: SoundConfig,
+    #[serde(default)]
+    pub theme: ThemeSetting,
     pub treble: ClefConfig,
     pub bass: ClefConfig,
 }
@@ -57,11 +240,23 @@ impl Config {
     const STORAGE_KEY: &str = "global-config";
 
     pub fn load() -> Self {
-        gloo_storage::LocalStorage::get(Self::STORAGE_KEY)
+        let mut config: Self = gloo_storage::LocalStorage::get(Self::STORAGE_KEY)
             .tap_err(|err| {
                 tracing::info!(?err, "failed to load global config");
             })
-            .unwrap_or_default()
+            .unwrap_or_default();
+
+        if let Some(device) = config.legacy_input_device.take() {
+            if config.input_devices.is_empty() && device != input::Device::Virtual {
+                config.input_devices.push(input::Selection {
+                    device,
+                    enabled: true,
+                    channels: Default::default(),
+                });
+            }
+        }
+
+        config
     }
 
     pub fn store(&self) {
@@ -69,21 +264,52 @@ impl Config {
             tracing::info!(?err, "failed to store global config");
         });
     }
+
+    pub fn is_device_enabled(&self, device: &input::Device) -> bool {
+        self.input_devices
+            .iter()
+            .any(|selection| selection.enabled && &selection.device == device)
+    }
+
+    /// Ports of the enabled midi devices, along with their channel settings.
+    pub fn midi_ports(&self) -> Vec<(input::PortDescriptor, input::channel::Channels)> {
+        self.input_devices
+            .iter()
+            .filter(|selection| selection.enabled)
+            .filter_map(|selection| match &selection.device {
+                input::Device::Midi(port) => Some((port.clone(), selection.channels)),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            input_device: input::Device::Virtual,
+            input_devices: Vec::new(),
+            legacy_input_device: None,
+            keyboard: KeyboardSize::default(),
+            naming: Naming::default(),
+            qwerty: input::qwerty::Mapping::default(),
+            hints: HintConfig::default(),
+            pedals: PedalBindings::default(),
+            output: input::output::Config::default(),
+            sound: SoundConfig::default(),
+            theme: ThemeSetting::default(),
             treble: ClefConfig {
                 clef: Clef::Treble,
                 range: OctaveRange::Fixed(2),
                 sharp_keys: false,
+                key_signature: KeySignature::default(),
+                scale: None,
             },
             bass: ClefConfig {
                 clef: Clef::Bass,
                 range: OctaveRange::Fixed(2),
                 sharp_keys: false,
+                key_signature: KeySignature::default(),
+                scale: None,
             },
         }
     }
@@ -93,11 +319,18 @@ impl Default for Config {
 pub struct ClefConfig {
     pub clef: Clef,
     pub range: OctaveRange,
+    /// Whether to include keys outside of the key signature.
     pub sharp_keys: bool,
+    #[serde(default)]
+    pub key_signature: KeySignature,
+    /// Restricts keys to the notes of the scale, instead of the key signature.
+    #[serde(default)]
+    pub scale: Option<Scale>,
 }
 
 impl ClefConfig {
-    pub fn to_key_range(&self) -> Option<Vec<Key>> {
+    /// Returns keys to practice, limited to the keys available on the keyboard.
+    pub fn to_key_range(&self, keyboard: &Keyboard) -> Option<Vec<Key>> {
         let (start, end) = match (self.clef, self.range) {
             (Clef::Treble, OctaveRange::Fixed(num)) if num <= 3 => {
                 (KeyPos::C.oct(4), KeyPos::B.oct(3 + num))
@@ -114,11 +347,17 @@ impl ClefConfig {
             _ => return None,
         };
 
+        let scale_keys = self.scale.map(|scale| scale.keys(keyboard));
+
         let range = keyboard::range(&start, &end)
-            .filter(|key| self.sharp_keys || key.is_natural())
-            .collect();
+            .filter(|key| keyboard.contains(key))
+            .filter(|key| match &scale_keys {
+                Some(scale_keys) => scale_keys.contains(key),
+                None => self.sharp_keys || self.key_signature.is_diatonic(*key),
+            })
+            .collect::<Vec<_>>();
 
-        Some(range)
+        (!range.is_empty()).then_some(range)
     }
 }
 
@@ -155,32 +394,117 @@ impl State {
 
 pub struct App {
     state: State,
+    theme: ThemeSetting,
+    show_help: bool,
+    notifications: notification::Notifications,
+    /// Midi file replayed by the replay input device, kept until the page is
+    /// reloaded.
+    replay: Option<Arc<Recording>>,
 }
 
 #[derive(From, Debug, Clone)]
 pub enum Message {
+    /// Multiple messages produced by a single event, processed in order.
+    Batch(Vec<Message>),
     StateTransition(StateTransition),
-    SelectInputPort(input::Device),
-    SelectOctaveRange { clef: Clef, range: OctaveRange },
-    ToggleSharpKeys { clef: Clef, enabled: bool },
+    Shortcut(shortcut::Shortcut),
+    Notify(notification::Notice),
+    DismissNotification(u64),
+    LoadingFailed(verovio::Error),
+    Reload,
+    ToggleInputDevice(input::Device, bool),
+    SelectChannels {
+        port: input::PortDescriptor,
+        channels: input::channel::Channels,
+    },
+    SelectOutput(input::output::Config),
+    SelectSound(SoundConfig),
+    SelectKeyboard(KeyboardSize),
+    SelectKeyboardRange {
+        first: Key,
+        last: Key,
+    },
+    SelectNaming(Naming),
+    SelectTheme(ThemeSetting),
+    SelectOctaveRange {
+        clef: Clef,
+        range: OctaveRange,
+    },
+    ToggleSharpKeys {
+        clef: Clef,
+        enabled: bool,
+    },
+    SelectKeySignature {
+        clef: Clef,
+        key_signature: KeySignature,
+    },
+    SelectScale {
+        clef: Clef,
+        scale: Option<Scale>,
+    },
+    SelectHintDelay(HintDelay),
+    SelectHintAttempts(HintAttempts),
+    SelectPedalAction {
+        pedal: Pedal,
+        action: PedalAction,
+    },
     RefreshDeviceList,
-    InputEvent(#[from] MidiMessage),
+    /// Opens a midi file for the replay input device.
+    OpenReplay,
+    ReplayLoaded(Arc<Recording>),
+    /// Downloads the recording of the game as a midi file.
+    SaveRecording,
+    /// Event of any of the input sources.
+    Input(input::Event),
+    QwertyInput {
+        /// Character of the pressed key, `None` if the key was released.
+        key: Option<char>,
+        code: iced::keyboard::key::Physical,
+        stamp: input::Timestamp,
+    },
+    SelectQwertyMapping(input::qwerty::Mapping),
     InputWorkerReady(input::Connector),
+    InputStatus {
+        port: input::PortDescriptor,
+        status: input::Status,
+    },
+    InputConnectionFailed(input::Error),
     Ready,
     AdvanceChallenge,
+    /// Stops the feedback sound played on the instrument.
+    ReleaseFeedback,
+    /// Plays the expected notes of the challenge.
+    PlayAnswer,
+    StopAnswer(Vec<Key>),
+    /// Reveals the next hint stage for the challenge with the given ID.
+    RevealHint(u64),
     ToggleVirtualKeyboard,
+    SelectKeyLabels(piano::KeyLabels),
+    ToggleMiddleCMarker(bool),
     ToggleFullscreen,
-    UpdateChallengeHint(widget::svg::Handle),
+    UpdateChallengeHint {
+        /// Id of the challenge the hint was rendered for.
+        id: u64,
+        hint: widget::svg::Handle,
+    },
 }
 
 impl App {
     pub fn boot() -> (Self, Task<Message>) {
+        let config = Config::load();
+
         (
             Self {
                 state: State::Loading(Default::default()),
+                theme: config.theme,
+                show_help: false,
+                notifications: Default::default(),
+                replay: None,
             },
-            Task::future(verovio::initialize())
-                .map(|_| Message::StateTransition(StateTransition::MainMenu)),
+            Task::future(verovio::initialize()).map(|res| match res {
+                Ok(_) => Message::StateTransition(StateTransition::MainMenu),
+                Err(err) => Message::LoadingFailed(err),
+            }),
         )
     }
 
@@ -193,7 +517,10 @@ impl App {
                     }
 
                     StateTransition::GameActive(settings) => {
-                        self.state = State::GameActive(game_active::State::new(settings));
+                        self.state = State::GameActive(game_active::State::new(
+                            settings,
+                            self.replay.clone(),
+                        ));
                     }
 
                     StateTransition::GameFinished(results) => {
@@ -204,13 +531,106 @@ impl App {
                 self.state.init()
             }
 
-            Message::ToggleFullscreen => {
-                let _ = util::toggle_fullscreen()
-                    .tap_err(|err| tracing::warn!(?err, "failed to toggle fullscreen"));
+            Message::Batch(messages) => {
+                let tasks = messages
+                    .into_iter()
+                    .map(|message| self.update(message))
+                    .collect::<Vec<_>>();
+
+                Task::batch(tasks)
+            }
+
+            Message::Shortcut(shortcut) => {
+                use shortcut::Shortcut;
+
+                let message = match (shortcut, &self.state) {
+                    (Shortcut::Help, _) => {
+                        self.show_help = !self.show_help;
+                        None
+                    }
+
+                    (Shortcut::MainMenu, _) if self.show_help => {
+                        self.show_help = false;
+                        None
+                    }
+
+                    (Shortcut::ToggleFullscreen, _) => Some(Message::ToggleFullscreen),
+                    (Shortcut::Play, State::MainMenu(state)) => state.play(),
+                    (Shortcut::Play, State::GameFinished(state)) => Some(state.play()),
+                    (Shortcut::Skip, State::GameActive(_)) => Some(Message::AdvanceChallenge),
+
+                    (Shortcut::ToggleKeyboard, State::GameActive(_)) => {
+                        Some(Message::ToggleVirtualKeyboard)
+                    }
+
+                    (Shortcut::MainMenu, State::GameActive(_) | State::GameFinished(_)) => {
+                        Some(StateTransition::MainMenu.into())
+                    }
+
+                    _ => None,
+                };
+
+                message.map_or_else(Task::none, |message| self.update(message))
+            }
+
+            Message::Notify(notice) => self.notifications.push(notice),
 
+            Message::DismissNotification(id) => {
+                self.notifications.dismiss(id);
                 Task::none()
             }
 
+            Message::ToggleFullscreen => match util::toggle_fullscreen() {
+                Ok(_) => Task::none(),
+                Err(err) => self.notifications.push(Notice::from_error(&err)),
+            },
+
+            Message::Reload => {
+                if let State::GameActive(state) = &mut self.state {
+                    state.all_notes_off();
+                }
+
+                match util::reload() {
+                    Ok(_) => Task::none(),
+                    Err(err) => self.notifications.push(Notice::from_error(&err)),
+                }
+            }
+
+            Message::OpenReplay => Task::future(async {
+                let (name, data) = match util::open_file(".mid,.midi,audio/midi").await {
+                    Ok(Some(file)) => file,
+                    // The dialog was cancelled.
+                    Ok(None) => return None,
+                    Err(err) => return Some(Message::Notify(Notice::from_error(&err))),
+                };
+
+                Some(match Recording::parse(name, &data) {
+                    Ok(recording) => Message::ReplayLoaded(Arc::new(recording)),
+                    Err(err) => Message::Notify(Notice::from_error(&err)),
+                })
+            })
+            .and_then(Task::done),
+
+            Message::ReplayLoaded(recording) => {
+                tracing::info!(
+                    name = recording.name,
+                    events = recording.events().len(),
+                    "replay loaded"
+                );
+
+                self.replay = Some(recording);
+                self.update(Message::ToggleInputDevice(input::Device::Replay, true))
+            }
+
+            Message::SelectTheme(theme) => {
+                self.theme = theme;
+
+                match &mut self.state {
+                    State::MainMenu(state) => state.update(Message::SelectTheme(theme)),
+                    _ => Task::none(),
+                }
+            }
+
             event => match &mut self.state {
                 State::Loading(state) => state.update(event),
                 State::MainMenu(state) => state.update(event),
@@ -234,6 +654,11 @@ impl App {
             .width(Length::Fill)
             .into();
 
+        let res = widget::stack![res]
+            .push_maybe((!self.notifications.is_empty()).then(|| self.notifications.view()))
+            .push_maybe(self.show_help.then(shortcut::help))
+            .into();
+
         if EXPLAIN_UI {
             res.explain(Color::BLACK)
         } else {
@@ -242,16 +667,18 @@ impl App {
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        match &self.state {
+        let state = match &self.state {
             State::Loading(state) => state.subscription(self),
             State::MainMenu(state) => state.subscription(self),
             State::GameActive(state) => state.subscription(self),
             State::GameFinished(state) => state.subscription(self),
-        }
+        };
+
+        Subscription::batch([state, iced::event::listen_with(shortcut::listen)])
     }
 
     pub fn theme(&self) -> Theme {
-        Theme::Light
+        self.theme.to_theme()
     }
 }
 
@@ -1,11 +1,29 @@
 use {
-    super::{App, Config, Font, Message},
+    super::{App, ClefConfig, Config, Font, HintDelay, Message, PedalAction, notification::Notice},
     crate::{
         app::StateTransition,
-        input::{self, Connector},
-        keyboard::{self, Key, KeyPos, Keyboard},
+        audio::{Audio, Layer},
+        input::{
+            self,
+            Connector,
+            InputSource,
+            ReplaySource,
+            Timestamp,
+            channel::Hand,
+            output::{self, Output},
+            pedal::{self, Pedal},
+            qwerty,
+            recording::{Recorder, Recording},
+        },
+        keyboard::{Accidental, Key, KeyPos, KeySignature, Keyboard, Pitch},
         piano::{self, Piano},
-        util,
+        synth::Effect,
+        util::{self, Hint as _},
+    },
+    futures_util::{
+        StreamExt as _,
+        future,
+        stream::{self, LocalBoxStream},
     },
     gloo_storage::Storage as _,
     iced::{
@@ -21,21 +39,36 @@ use {
     serde::{Deserialize, Serialize},
     sheet::{Note, Sheet},
     smallvec::SmallVec,
-    std::{borrow::Cow, collections::HashSet},
+    std::{
+        borrow::Cow,
+        collections::{HashMap, HashSet, VecDeque},
+        convert,
+        sync::Arc,
+        time::Duration,
+    },
     tap::TapFallible as _,
 };
 
 mod sheet;
 
+const ANSWER_VELOCITY: u8 = 80;
+/// Duration of the notes played as the answer, in milliseconds.
+const ANSWER_DURATION: i32 = 1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LocalConfig {
     pub virtual_keyboard: bool,
+    pub key_labels: piano::KeyLabels,
+    pub middle_c_marker: bool,
 }
 
 impl Default for LocalConfig {
     fn default() -> Self {
         Self {
             virtual_keyboard: true,
+            key_labels: piano::KeyLabels::default(),
+            middle_c_marker: true,
         }
     }
 }
@@ -61,134 +94,339 @@ impl LocalConfig {
 pub struct State {
     config: Config,
     local_config: LocalConfig,
+    keyboard: Keyboard,
     initialized: bool,
     input: Option<Connector>,
+    /// Connection status of each of the enabled midi devices.
+    input_status: Vec<(input::PortDescriptor, input::Status)>,
+    qwerty: qwerty::Mapper,
+    /// Pedals and keys held on each midi port, and on the other input sources.
+    pedals: HashMap<Option<input::PortId>, pedal::Tracker>,
+    /// Built-in synthesizer, if audio is available.
+    audio: Option<Audio>,
+    /// Output to the instrument, if one is selected. All notes are turned off
+    /// once the game is left.
+    output: Option<Output<input::MidiSink>>,
+    /// Turns off all notes of the output once the page is closed, since the
+    /// game isn't left then.
+    exit_listener: Option<util::EventListener>,
+    /// Input sources connected once the game is ready, e.g. the replayed midi
+    /// file. Midi devices are connected by the input worker instead. The
+    /// sources are disconnected once the game is left.
+    sources: Vec<Box<dyn InputSource>>,
+    /// Input events and challenges of the game, which can be downloaded as a
+    /// midi file.
+    recorder: Recorder,
+    /// Challenges of the replayed midi file, which are given before any
+    /// randomly chosen ones.
+    script: VecDeque<Vec<Pitch>>,
     range_treble: Option<Vec<Key>>,
     range_bass: Option<Vec<Key>>,
     curr_challenge: Option<Challenge>,
     prev_challenge: Option<Challenge>,
+    last_challenge_id: u64,
+    /// Time from displaying each completed challenge to playing its last key.
+    reaction_times: Vec<Duration>,
     hint: Option<widget::svg::Handle>,
+    /// CSS color of the rendered staff, matching the theme.
+    staff_color: String,
     piano: Piano,
 }
 
 impl State {
-    pub fn new(config: Config) -> Self {
-        let range_treble = config.treble.to_key_range();
-        let range_bass = config.bass.to_key_range();
+    pub fn new(config: Config, replay: Option<Arc<Recording>>) -> Self {
+        let audio = Audio::new()
+            .tap_err(|err| tracing::warn!(?err, "failed to initialize audio"))
+            .ok();
+        let local_config = LocalConfig::load();
+        let replay = replay.filter(|_| config.is_device_enabled(&input::Device::Replay));
+        let script = replay
+            .iter()
+            .flat_map(|recording| recording.challenges())
+            .map(|(_, pitches)| pitches.clone())
+            .collect();
+        let sources = replay
+            .map(|recording| Box::new(ReplaySource::new(recording)) as Box<dyn InputSource>)
+            .into_iter()
+            .collect();
+
+        let mut state = Self::with_sources(config, local_config, audio, sources);
+        state.script = script;
+        state
+    }
+
+    /// Creates the game with the given input sources, which are connected once
+    /// the game is ready.
+    fn with_sources(
+        config: Config,
+        local_config: LocalConfig,
+        audio: Option<Audio>,
+        sources: Vec<Box<dyn InputSource>>,
+    ) -> Self {
+        let keyboard = config.keyboard.to_keyboard();
+        let range_treble = config.treble.to_key_range(&keyboard);
+        let range_bass = config.bass.to_key_range(&keyboard);
+        let qwerty = qwerty::Mapper::new(config.qwerty.clone());
+        let staff_color = config.theme.staff_color();
+
+        let mut piano = Piano::new(keyboard.clone());
+        piano.set_labels(local_config.key_labels);
+        piano.set_naming(config.naming);
+        piano.set_middle_c_marker(local_config.middle_c_marker);
+
+        // Focus the on-screen keyboard on the practiced keys.
+        let practiced = range_treble.iter().chain(&range_bass).flatten();
+        if let (Some(first), Some(last)) = (practiced.clone().min(), practiced.max()) {
+            piano.set_focus(*first, *last);
+        }
 
         Self {
             config,
-            local_config: LocalConfig::load(),
+            local_config,
+            keyboard,
             initialized: false,
             input: None,
+            input_status: Vec::new(),
+            qwerty,
+            pedals: HashMap::new(),
+            output: None,
+            exit_listener: None,
+            audio,
+            sources,
+            recorder: Recorder::default(),
+            script: VecDeque::new(),
             range_treble,
             range_bass,
             curr_challenge: None,
             prev_challenge: None,
+            last_challenge_id: 0,
+            reaction_times: Vec::new(),
             hint: None,
-            piano: Piano::new(keyboard::Keyboard::standard_88_key()),
+            staff_color,
+            piano,
         }
     }
 
     pub fn init(&mut self) -> Task<Message> {
-        Task::none()
+        let output::Device::Midi(port) = &self.config.output.device else {
+            return Task::none();
+        };
+
+        match input::connect_output(port) {
+            Ok(sink) => {
+                let mut exit_sink = sink.clone();
+
+                self.exit_listener =
+                    util::on_page_hide(move || output::all_notes_off(&mut exit_sink))
+                        .tap_err(|err| tracing::warn!(?err, "failed to listen to page hide"))
+                        .ok();
+                self.output = Some(Output::new(sink, self.config.output.clone()));
+                Task::none()
+            }
+
+            Err(err) => {
+                tracing::warn!(?err, "failed to connect output port");
+
+                Task::done(Message::Notify(Notice::warning(err.describe())))
+            }
+        }
     }
 
     pub fn update(&mut self, event: Message) -> Task<Message> {
         match event {
             Message::InputWorkerReady(connector) => {
                 self.input = Some(connector.clone());
-                let device = self.config.input_device.clone();
+                let ports = self.config.midi_ports();
 
-                return match device {
-                    input::Device::Virtual => Task::done(Message::Ready),
+                if ports.is_empty() {
+                    return Task::done(Message::Ready);
+                }
 
-                    input::Device::Midi(port) => Task::future(async move {
-                        match connector.connect(port).await {
-                            Ok(_) => Message::Ready,
+                return Task::future(async move {
+                    match connector.connect(ports).await {
+                        Ok(_) => Message::Ready,
+                        Err(err) => Message::InputConnectionFailed(err),
+                    }
+                });
+            }
 
-                            Err(err) => {
-                                tracing::warn!(?err, "failed to connect input port");
-                                // TODO: Reset input device selection.
-                                StateTransition::MainMenu.into()
-                            }
-                        }
-                    }),
+            Message::InputConnectionFailed(err) => {
+                tracing::warn!(?err, "failed to connect input port");
+
+                // Disable the midi devices, so the game can be started with the
+                // other input devices.
+                for selection in &mut self.config.input_devices {
+                    if let input::Device::Midi(_) = selection.device {
+                        selection.enabled = false;
+                    }
+                }
+
+                self.config.store();
+
+                let notice = Notice::from_error(&err);
+
+                return Task::batch([
+                    Task::done(Message::Notify(notice)),
+                    Task::done(StateTransition::MainMenu.into()),
+                ]);
+            }
+
+            Message::InputStatus { port, status } => {
+                tracing::info!(%port, %status, "input status changed");
+
+                let port_id = port.port_id();
+
+                let entry = self
+                    .input_status
+                    .iter_mut()
+                    .find(|(entry_port, _)| *entry_port == port);
+                let prev_status = entry.as_ref().map(|(_, prev)| *prev);
+
+                let notice = match (prev_status, status) {
+                    (_, input::Status::Disconnected) => Some(Notice::warning(format!(
+                        "MIDI device '{port}' disconnected. Plug it back in to continue."
+                    ))),
+                    (Some(input::Status::Disconnected), input::Status::Connected) => {
+                        Some(Notice::info(format!("MIDI device '{port}' reconnected.")))
+                    }
+                    _ => None,
                 };
+
+                match entry {
+                    Some((_, entry_status)) => *entry_status = status,
+                    None => self.input_status.push((port, status)),
+                }
+
+                // The note and pedal offs of a disconnected device never arrive. Keys
+                // and pedals held on the other devices are kept.
+                let released = if status == input::Status::Disconnected {
+                    let events = self
+                        .pedals
+                        .remove(&Some(port_id))
+                        .map(|mut tracker| tracker.reset())
+                        .unwrap_or_default();
+                    self.handle_tracked(events, None, Timestamp::now())
+                } else {
+                    Task::none()
+                };
+
+                let notice =
+                    notice.map_or_else(Task::none, |notice| Task::done(Message::Notify(notice)));
+
+                return Task::batch([released, notice]);
             }
 
             Message::Ready => {
                 tracing::info!("port connected");
                 self.initialized = true;
-                return self.advance();
+                self.recorder.start(Timestamp::now());
+                let events = self.connect_sources();
+                return Task::batch([self.advance(), Task::run(events, convert::identity)]);
             }
 
-            Message::UpdateChallengeHint(hint) => {
-                self.hint = Some(hint);
-            }
+            Message::UpdateChallengeHint { id, hint } => match &mut self.curr_challenge {
+                Some(challenge) if challenge.id == id => {
+                    challenge.shown.get_or_insert_with(Timestamp::now);
+                    self.hint = Some(hint);
+                }
+
+                // The completed challenge is shown until the next one starts.
+                None if self
+                    .prev_challenge
+                    .as_ref()
+                    .is_some_and(|challenge| challenge.id == id) =>
+                {
+                    self.hint = Some(hint);
+                }
+
+                // Hints of the previous challenges may still be rendering.
+                _ => {}
+            },
 
             Message::AdvanceChallenge => {
                 return self.advance();
             }
 
-            Message::ToggleVirtualKeyboard => {
-                self.local_config.virtual_keyboard = !self.local_config.virtual_keyboard;
-                self.local_config.store();
-            }
+            Message::SaveRecording => {
+                let result = match self.recorder.to_smf() {
+                    Ok(data) => util::download("clef-rush-session.mid", "audio/midi", &data)
+                        .map_err(|err| Notice::from_error(&err)),
+                    Err(err) => Err(Notice::from_error(&err)),
+                };
 
-            Message::InputEvent(msg) => match msg {
-                MidiMessage::NoteOn { key, vel } => {
-                    if let Ok(key) = Key::try_from_midi(key) {
-                        tracing::info!(?key, ?vel, "midi message: note on");
+                if let Err(notice) = result {
+                    return Task::done(Message::Notify(notice));
+                }
+            }
 
-                        if let Some(challenge) = &mut self.curr_challenge {
-                            self.piano.set_key_state(key, piano::KeyState::Pressed);
+            Message::ReleaseFeedback => {
+                if let Some(output) = &mut self.output {
+                    output.release_feedback();
+                }
+            }
 
-                            if challenge.validator.validate(key) {
-                                tracing::info!(?key, "correct key");
+            Message::PlayAnswer => {
+                let (Some(challenge), Some(audio)) = (&self.curr_challenge, &mut self.audio) else {
+                    return Task::none();
+                };
 
-                                challenge.sheet.set_note_style(key, sheet::Style::Correct);
+                let keys = challenge.validator.keys().collect::<Vec<_>>();
 
-                                if challenge.validator.finished() {
-                                    let tasks = Task::batch([
-                                        self.update_hint(),
-                                        Task::future(async {
-                                            util::sleep(500).await;
-                                            Message::AdvanceChallenge
-                                        }),
-                                    ]);
+                for key in &keys {
+                    audio.note_on(Layer::Answer, key.to_midi(), ANSWER_VELOCITY.into());
+                }
 
-                                    self.prev_challenge = self.curr_challenge.take();
-                                    return tasks;
-                                }
-                            } else {
-                                challenge.sheet.add_note(key, sheet::Style::Incorrect);
+                return Task::future(async move {
+                    util::sleep(ANSWER_DURATION).await;
+                    Message::StopAnswer(keys)
+                });
+            }
 
-                                tracing::info!(?key, "incorrect key");
-                            }
+            Message::StopAnswer(keys) => {
+                if let Some(audio) = &mut self.audio {
+                    for key in keys {
+                        audio.note_off(Layer::Answer, key.to_midi());
+                    }
+                }
+            }
 
-                            return self.update_hint();
-                        }
-                    };
+            Message::RevealHint(id) => {
+                if self.curr_challenge.as_ref().map(|challenge| challenge.id) == Some(id) {
+                    self.reveal_hint();
                 }
+            }
+
+            Message::ToggleVirtualKeyboard => {
+                self.local_config.virtual_keyboard = !self.local_config.virtual_keyboard;
+                self.local_config.store();
+            }
 
-                MidiMessage::NoteOff { key, vel } => {
-                    if let Ok(key) = Key::try_from_midi(key) {
-                        tracing::info!(?key, ?vel, "midi message: note off");
+            Message::SelectKeyLabels(labels) => {
+                self.piano.set_labels(labels);
+                self.local_config.key_labels = labels;
+                self.local_config.store();
+            }
 
-                        self.piano.set_key_state(key, piano::KeyState::Released);
+            Message::ToggleMiddleCMarker(enabled) => {
+                self.piano.set_middle_c_marker(enabled);
+                self.local_config.middle_c_marker = enabled;
+                self.local_config.store();
+            }
 
-                        if let Some(challenge) = &mut self.curr_challenge {
-                            if !challenge.validator.required(key) {
-                                challenge.sheet.remove_note(key);
-                                return self.update_hint();
-                            }
-                        }
-                    }
+            Message::QwertyInput { key, code, stamp } => {
+                let msg = match key {
+                    Some(key) => self.qwerty.key_pressed(key, code),
+                    None => self.qwerty.key_released(code),
+                };
+
+                if let Some(message) = msg {
+                    return self.handle_input(input::Event::new(message, stamp));
                 }
+            }
 
-                _ => {}
-            },
+            Message::Input(event) => {
+                return self.handle_input(event);
+            }
 
             _ => {}
         }
@@ -197,15 +435,80 @@ impl State {
     }
 
     pub fn view<'a>(&'a self, _: &'a App) -> Element<'a, Message> {
+        let piano_overlays = self.local_config.virtual_keyboard.then(|| {
+            widget::row![
+                widget::pick_list(
+                    &piano::KeyLabels::ALL[..],
+                    Some(self.local_config.key_labels),
+                    Message::SelectKeyLabels,
+                ),
+                widget::checkbox("Middle C", self.local_config.middle_c_marker)
+                    .on_toggle(Message::ToggleMiddleCMarker),
+            ]
+            .spacing(20)
+            .align_y(alignment::Vertical::Center)
+        });
+
+        let input_status = (!self.input_status.is_empty()).then(|| {
+            let total = self.input_status.len();
+            let connected = self
+                .input_status
+                .iter()
+                .filter(|(_, status)| *status == input::Status::Connected)
+                .count();
+
+            let label = match (connected, total) {
+                (1, 1) => widget::text(format!("MIDI: {}", input::Status::Connected)),
+                (0, 1) => widget::text(format!("MIDI: {}", input::Status::Disconnected)),
+                _ => widget::text(format!("MIDI: {connected} of {total} connected")),
+            };
+
+            if connected == total {
+                label.style(widget::text::success)
+            } else {
+                label.style(widget::text::danger)
+            }
+        });
+
+        let reaction_time = self.reaction_times.last().map(|last| {
+            let average =
+                self.reaction_times.iter().sum::<Duration>() / self.reaction_times.len() as u32;
+
+            widget::text(format!(
+                "Reaction: {:.2} s (avg. {:.2} s)",
+                last.as_secs_f32(),
+                average.as_secs_f32()
+            ))
+        });
+
         let header = widget::row![
             widget::text(super::TITLE).size(36).font(Font::Title),
             widget::horizontal_space(),
-            widget::button("Toggle Fullscreen").on_press(Message::ToggleFullscreen),
-            widget::button("Toggle Keyboard").on_press(Message::ToggleVirtualKeyboard),
-            widget::button("Skip").on_press(Message::AdvanceChallenge),
-            widget::button("Main Menu")
-                .on_press(Message::StateTransition(StateTransition::MainMenu))
         ]
+        .push_maybe(reaction_time)
+        .push_maybe(input_status)
+        .push_maybe(piano_overlays)
+        .push(widget::button("Toggle Fullscreen").on_press(Message::ToggleFullscreen))
+        .push(widget::button("Toggle Keyboard").on_press(Message::ToggleVirtualKeyboard))
+        .push(
+            widget::button("Hint").on_press_maybe(
+                self.curr_challenge
+                    .as_ref()
+                    .map(|challenge| Message::RevealHint(challenge.id)),
+            ),
+        )
+        .push(widget::button("Play Answer").on_press_maybe(
+            (self.audio.is_some() && self.curr_challenge.is_some()).then_some(Message::PlayAnswer),
+        ))
+        .push(widget::button("Skip").on_press(Message::AdvanceChallenge))
+        .push(
+            widget::button("Save Recording")
+                .on_press_maybe((!self.recorder.is_empty()).then_some(Message::SaveRecording)),
+        )
+        .push(
+            widget::button("Main Menu")
+                .on_press(Message::StateTransition(StateTransition::MainMenu)),
+        )
         .spacing(20)
         .align_y(alignment::Vertical::Center)
         .width(Length::Fill);
@@ -246,19 +549,304 @@ impl State {
     }
 
     pub fn subscription<'a>(&'a self, _: &'a App) -> Subscription<Message> {
-        Subscription::run(input::connection_worker)
+        let qwerty = self
+            .config
+            .is_device_enabled(&input::Device::Qwerty)
+            .then(|| iced::event::listen_with(qwerty::listen));
+
+        Subscription::batch(
+            [Subscription::run(input::connection_worker)]
+                .into_iter()
+                .chain(qwerty),
+        )
+    }
+
+    /// Turns off all notes of the output, e.g. before the page is reloaded.
+    pub fn all_notes_off(&mut self) {
+        if let Some(output) = &mut self.output {
+            output.all_notes_off();
+        }
+    }
+
+    /// Handles an event of any of the input sources, including the on-screen
+    /// and the computer keyboards.
+    fn handle_input(&mut self, event: input::Event) -> Task<Message> {
+        self.recorder.record(&event);
+
+        let events = self
+            .pedals
+            .entry(event.port)
+            .or_insert_with(|| pedal::Tracker::new(self.config.pedals.bound()))
+            .process(event.message);
+        self.handle_tracked(events, event.hand, event.stamp)
+    }
+
+    /// Handles the note and pedal events produced by the pedal tracker.
+    fn handle_tracked(
+        &mut self,
+        events: Vec<pedal::Event>,
+        hand: Option<Hand>,
+        stamp: Timestamp,
+    ) -> Task<Message> {
+        let tasks = events
+            .into_iter()
+            .map(|event| match event {
+                pedal::Event::Note(msg) => self.handle_note(msg, hand, stamp),
+                pedal::Event::Pedal { pedal, pressed } => self.handle_pedal(pedal, pressed),
+            })
+            .collect::<Vec<_>>();
+
+        Task::batch(tasks)
+    }
+
+    /// Connects the input sources, returning the merged stream of their events
+    /// and of the notices of the sources which failed to connect.
+    fn connect_sources(&mut self) -> LocalBoxStream<'static, Message> {
+        let streams = self
+            .sources
+            .iter_mut()
+            .map(|source| match source.connect() {
+                Ok(events) => events.map(Message::Input).boxed_local(),
+                Err(err) => {
+                    let notice = Message::Notify(Notice::from_error(&err));
+                    stream::once(future::ready(notice)).boxed_local()
+                }
+            });
+
+        stream::select_all(streams).boxed_local()
+    }
+
+    fn handle_pedal(&mut self, pedal: Pedal, pressed: bool) -> Task<Message> {
+        tracing::info!(%pedal, pressed, "midi message: pedal");
+
+        let action = self.config.pedals.get(pedal);
+
+        if action == PedalAction::None {
+            self.piano.set_pedal(pedal, pressed);
+            return Task::none();
+        }
+
+        if !pressed {
+            return Task::none();
+        }
+
+        match action {
+            PedalAction::None => Task::none(),
+            PedalAction::Skip => Task::done(Message::AdvanceChallenge),
+            PedalAction::Hint => self
+                .curr_challenge
+                .as_ref()
+                .map_or_else(Task::none, |challenge| {
+                    Task::done(Message::RevealHint(challenge.id))
+                }),
+        }
+    }
+
+    /// Handles a note played on any of the input devices. If the channel of the
+    /// device is mapped to a hand, the note must be played with the hand of its
+    /// clef. The timestamp of the note is used to measure the reaction time.
+    fn handle_note(
+        &mut self,
+        msg: MidiMessage,
+        hand: Option<Hand>,
+        stamp: Timestamp,
+    ) -> Task<Message> {
+        match msg {
+            MidiMessage::NoteOn { key, vel } => {
+                if self.config.sound.notes {
+                    if let Some(audio) = &mut self.audio {
+                        audio.note_on(Layer::Input, key, vel);
+                    }
+                }
+
+                if let Ok(key) = Key::try_from_midi(key) {
+                    tracing::info!(%key, ?vel, ?hand, "midi message: note on");
+
+                    // Keys pressed between challenges aren't validated.
+                    self.piano.set_key_state(key, piano::KeyState::Pressed);
+
+                    let is_correct_hand = hand.is_none_or(|hand| hand == self.expected_hand(key));
+
+                    if let Some(challenge) = &mut self.curr_challenge {
+                        let mut reveal = false;
+                        let mut is_incorrect = false;
+
+                        if is_correct_hand && challenge.validator.validate(key) {
+                            tracing::info!(%key, "correct key");
+
+                            self.piano.set_key_state(key, piano::KeyState::Correct);
+                            challenge.sheet.set_note_style(key, sheet::Style::Correct);
+
+                            if let Some(output) = &mut self.output {
+                                output.unlight_key(key.to_midi());
+                            }
+
+                            if challenge.validator.finished() {
+                                // Challenges completed before their sheet is displayed
+                                // have no reaction time.
+                                if let Some(shown) = challenge.shown {
+                                    let reaction_time = stamp.duration_since(shown);
+                                    tracing::info!(?reaction_time, "challenge completed");
+
+                                    self.reaction_times.push(reaction_time);
+                                }
+
+                                self.play_effect(Effect::Click);
+
+                                let tasks = Task::batch([
+                                    self.update_hint(),
+                                    self.play_feedback(true),
+                                    Task::future(async {
+                                        util::sleep(500).await;
+                                        Message::AdvanceChallenge
+                                    }),
+                                ]);
+
+                                self.prev_challenge = self.curr_challenge.take();
+                                return tasks;
+                            }
+                        } else {
+                            self.piano.set_key_state(key, piano::KeyState::Incorrect);
+
+                            if challenge.validator.required(key) {
+                                tracing::info!(%key, "wrong hand");
+                                challenge.sheet.set_note_style(key, sheet::Style::Incorrect);
+                            } else {
+                                challenge.sheet.add_note(key, sheet::Style::Incorrect);
+                            }
+
+                            challenge.wrong_attempts += 1;
+                            is_incorrect = true;
+
+                            reveal = self
+                                .config
+                                .hints
+                                .attempts
+                                .should_reveal(challenge.wrong_attempts);
+
+                            tracing::info!(%key, "incorrect key");
+                        }
+
+                        if reveal {
+                            self.reveal_hint();
+                        }
+
+                        let feedback = if is_incorrect {
+                            self.play_effect(Effect::Error);
+                            self.play_feedback(false)
+                        } else {
+                            self.play_effect(Effect::Click);
+                            Task::none()
+                        };
+
+                        return Task::batch([self.update_hint(), feedback]);
+                    }
+                };
+            }
+
+            MidiMessage::NoteOff { key, vel } => {
+                if let Some(audio) = &mut self.audio {
+                    audio.note_off(Layer::Input, key);
+                }
+
+                if let Ok(key) = Key::try_from_midi(key) {
+                    tracing::info!(%key, ?vel, "midi message: note off");
+
+                    self.piano.set_key_state(key, piano::KeyState::Released);
+
+                    if let Some(challenge) = &mut self.curr_challenge {
+                        if !challenge.validator.required(key) {
+                            challenge.sheet.remove_note(key);
+                            return self.update_hint();
+                        }
+
+                        // Restore the style of a note played with the wrong hand.
+                        if hand.is_some() {
+                            let style = if challenge.validator.is_validated(key) {
+                                sheet::Style::Correct
+                            } else {
+                                sheet::Style::Default
+                            };
+
+                            challenge.sheet.set_note_style(key, style);
+                            return self.update_hint();
+                        }
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        Task::none()
+    }
+
+    fn play_effect(&mut self, effect: Effect) {
+        if let (true, Some(audio)) = (self.config.sound.effects, &mut self.audio) {
+            audio.play_effect(effect);
+        }
+    }
+
+    /// Plays the feedback sound on the instrument, if enabled.
+    fn play_feedback(&mut self, correct: bool) -> Task<Message> {
+        match &mut self.output {
+            Some(output) if output.play_feedback(correct) => Task::future(async {
+                util::sleep(output::FEEDBACK_DURATION).await;
+                Message::ReleaseFeedback
+            }),
+            _ => Task::none(),
+        }
+    }
+
+    /// Returns the hand expected to play the key, based on its clef.
+    fn expected_hand(&self, key: Key) -> Hand {
+        if key >= self.clef_split() {
+            Hand::Right
+        } else {
+            Hand::Left
+        }
     }
 
     fn clef_split(&self) -> Key {
-        let kbd = Keyboard::standard_88_key();
         match (&self.range_treble, &self.range_bass) {
-            (None, Some(_)) => kbd.last(),
-            (Some(_), None) => kbd.first(),
+            (None, Some(_)) => self.keyboard.last(),
+            (Some(_), None) => self.keyboard.first(),
             _ => KeyPos::C.oct(4),
         }
     }
 
     fn advance(&mut self) -> Task<Message> {
+        let notes: SmallVec<[_; 2]> = match self.script.pop_front() {
+            Some(pitches) => pitches
+                .into_iter()
+                .filter_map(|pitch| Some(Note::spelled(pitch.to_key().ok()?, pitch)))
+                .collect(),
+            None => self.choose_notes(),
+        };
+
+        self.recorder
+            .record_challenge(Timestamp::now(), notes.iter().map(|note| note.pitch));
+
+        self.last_challenge_id += 1;
+        self.curr_challenge = Some(Challenge::new(
+            self.last_challenge_id,
+            &notes,
+            self.clef_split(),
+            self.config.treble.key_signature,
+            self.config.bass.key_signature,
+        ));
+        self.piano.clear_highlights();
+
+        if let Some(output) = &mut self.output {
+            output.light_keys(notes.iter().map(|note| note.key.to_midi()));
+        }
+
+        Task::batch([self.update_hint(), self.schedule_reveal()])
+    }
+
+    /// Randomly chooses the notes of the next challenge from the practiced
+    /// ranges.
+    fn choose_notes(&self) -> SmallVec<[Note; 2]> {
         let choose_note = |range: &Vec<Key>| {
             loop {
                 let key = range[..].choose(&mut rand::rng()).unwrap();
@@ -269,18 +857,94 @@ impl State {
                     .map(|challenge| challenge.validator.required(*key))
                     .unwrap_or(false);
 
-                if !is_repeated {
+                if !is_repeated || range.len() == 1 {
                     break *key;
                 }
             }
         };
 
-        let treble = self.range_treble.as_ref().map(choose_note).map(Note::from);
-        let bass = self.range_bass.as_ref().map(choose_note).map(Note::from);
-        let notes = treble.into_iter().chain(bass).collect::<SmallVec<[_; 2]>>();
+        // Notes are spelled according to the selected scale, if any. Notes outside
+        // of the key signature are spelled with the signature's accidentals, or
+        // randomly either as sharps or flats in C major.
+        let spell_note = |key: Key, config: &ClefConfig| {
+            let key_signature = config.key_signature;
+            let scale_pitch = config.scale.and_then(|scale| scale.spell(key));
 
-        self.curr_challenge = Some(Challenge::new(&notes, self.clef_split()));
-        self.update_hint()
+            let pitch = match scale_pitch.or_else(|| key_signature.spell(key)) {
+                Some(pitch) => pitch,
+                None if key_signature.fifths() == 0 && rand::random() => {
+                    key.spell(Accidental::Flat)
+                }
+                None => key_signature.spell_chromatic(key),
+            };
+
+            Note::spelled(key, pitch)
+        };
+
+        let treble = self
+            .range_treble
+            .as_ref()
+            .map(choose_note)
+            .map(|key| spell_note(key, &self.config.treble));
+        let bass = self
+            .range_bass
+            .as_ref()
+            .map(choose_note)
+            .map(|key| spell_note(key, &self.config.bass));
+
+        treble.into_iter().chain(bass).collect()
+    }
+
+    /// Schedules the hint stages to be revealed after the configured delay.
+    fn schedule_reveal(&self) -> Task<Message> {
+        let (Some(challenge), HintDelay::Seconds(secs)) =
+            (&self.curr_challenge, self.config.hints.delay)
+        else {
+            return Task::none();
+        };
+
+        let id = challenge.id;
+
+        Task::batch([1, 2].map(|stage| {
+            Task::future(async move {
+                util::sleep((secs * stage * 1000) as i32).await;
+                Message::RevealHint(id)
+            })
+        }))
+    }
+
+    /// Reveals the next hint stage on the on-screen keyboard: first all keys
+    /// with the pitch class of the expected keys, then the expected keys.
+    fn reveal_hint(&mut self) {
+        let Some(challenge) = &mut self.curr_challenge else {
+            return;
+        };
+
+        challenge.reveal = challenge.reveal.next();
+
+        let expected = &challenge.validator.expected;
+
+        match challenge.reveal {
+            Reveal::None => {}
+
+            Reveal::PitchClass => {
+                for key in self.keyboard.iter_keys() {
+                    if expected.iter().any(|expected| expected.pos == key.pos) {
+                        self.piano
+                            .set_highlight(key, Some(piano::Highlight::Hinted));
+                    }
+                }
+            }
+
+            Reveal::Key => {
+                for key in expected {
+                    self.piano
+                        .set_highlight(*key, Some(piano::Highlight::Expected));
+                }
+            }
+        }
+
+        tracing::info!(reveal = ?challenge.reveal, "hint revealed");
     }
 
     fn update_hint(&self) -> Task<Message> {
@@ -288,28 +952,72 @@ impl State {
             return Task::none();
         };
 
-        let hint_fut = challenge.sheet.render_hint_svg();
+        let id = challenge.id;
+        let hint_fut = challenge.sheet.render_hint_svg(&self.staff_color);
 
         Task::future(async move {
             let instant = instant::Instant::now();
-            let hint = hint_fut.await;
-            tracing::info!(elapsed = ?instant.elapsed(), "generated svg");
+            match hint_fut.await {
+                Ok(hint) => {
+                    tracing::info!(elapsed = ?instant.elapsed(), "generated svg");
 
-            Message::UpdateChallengeHint(widget::svg::Handle::from_memory(Cow::Owned(
-                hint.as_bytes().into(),
-            )))
+                    Message::UpdateChallengeHint {
+                        id,
+                        hint: widget::svg::Handle::from_memory(Cow::Owned(hint.as_bytes().into())),
+                    }
+                }
+
+                Err(err) => Message::Notify(Notice::from_error(&err)),
+            }
         })
     }
 }
 
+impl Drop for State {
+    fn drop(&mut self) {
+        for source in &mut self.sources {
+            source.disconnect();
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum Reveal {
+    #[default]
+    None,
+    PitchClass,
+    Key,
+}
+
+impl Reveal {
+    fn next(self) -> Self {
+        match self {
+            Self::None => Self::PitchClass,
+            Self::PitchClass | Self::Key => Self::Key,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Challenge {
+    id: u64,
+    /// Time the sheet of the challenge was first displayed, which happens once
+    /// it's rendered.
+    shown: Option<Timestamp>,
     validator: Validator,
     sheet: Sheet,
+    reveal: Reveal,
+    wrong_attempts: u32,
 }
 
 impl Challenge {
-    fn new(notes: &[Note], clef_split: Key) -> Self {
+    fn new(
+        id: u64,
+        notes: &[Note],
+        clef_split: Key,
+        treble_key_signature: KeySignature,
+        bass_key_signature: KeySignature,
+    ) -> Self {
         let mut treble_ntoes = false;
         let mut bass_notes = false;
 
@@ -328,8 +1036,18 @@ impl Challenge {
         };
 
         Self {
+            id,
+            shown: None,
             validator: Validator::new(notes),
-            sheet: Sheet::new(mode, notes, clef_split),
+            sheet: Sheet::new(
+                mode,
+                notes,
+                clef_split,
+                treble_key_signature,
+                bass_key_signature,
+            ),
+            reveal: Reveal::None,
+            wrong_attempts: 0,
         }
     }
 }
@@ -367,4 +1085,183 @@ impl Validator {
     fn required(&self, key: Key) -> bool {
         self.expected.contains(&key) || self.validated.contains(&key)
     }
+
+    fn is_validated(&self, key: Key) -> bool {
+        self.validated.contains(&key)
+    }
+
+    fn keys(&self) -> impl Iterator<Item = Key> {
+        self.expected.iter().chain(&self.validated).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            input::{
+                fixtures::{note_off, note_on, port},
+                source::FakeSource,
+            },
+            keyboard::Letter,
+        },
+        futures_util::{FutureExt as _, StreamExt as _},
+        wasm_bindgen_test::*,
+    };
+
+    /// Source which fails to connect, like an unplugged device.
+    struct UnavailableSource;
+
+    impl InputSource for UnavailableSource {
+        fn connect(&mut self) -> Result<input::EventStream, input::Error> {
+            Err(input::Error::PortNotAvailable)
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    /// Starts a game with a challenge of C4 and A3, handling the messages of
+    /// the connected sources. Returns the notices of the sources which
+    /// failed to connect.
+    fn start(sources: Vec<Box<dyn InputSource>>) -> (State, Vec<Notice>) {
+        let mut state =
+            State::with_sources(Config::default(), LocalConfig::default(), None, sources);
+
+        state.script.push_back(vec![
+            Pitch::new(Letter::C, Accidental::Natural, 4),
+            Pitch::new(Letter::A, Accidental::Natural, 3),
+        ]);
+
+        let _ = state.advance();
+
+        let mut notices = Vec::new();
+        let mut messages = state.connect_sources();
+
+        while let Some(Some(message)) = messages.next().now_or_never() {
+            match message {
+                Message::Notify(notice) => notices.push(notice),
+                message => {
+                    let _ = state.update(message);
+                }
+            }
+        }
+
+        (state, notices)
+    }
+
+    /// Starts a game, playing the events of the fake source.
+    fn play(events: impl IntoIterator<Item = input::Event>) -> State {
+        let (state, notices) = start(vec![Box::new(FakeSource::new(events))]);
+        assert!(notices.is_empty());
+        state
+    }
+
+    fn event(message: MidiMessage, hand: Option<Hand>) -> input::Event {
+        input::Event {
+            hand,
+            ..input::Event::new(message, Timestamp::from_micros(1000))
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn correct_keys() {
+        let state = play([
+            event(note_on(60), None),
+            event(note_off(60), None),
+            event(note_on(57), None),
+        ]);
+
+        let challenge = state.prev_challenge.as_ref().unwrap();
+        assert!(state.curr_challenge.is_none());
+        assert!(challenge.validator.finished());
+        assert_eq!(challenge.wrong_attempts, 0);
+        assert!(!state.recorder.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn wrong_key() {
+        let state = play([
+            event(note_on(62), None),
+            event(note_off(62), None),
+            event(note_on(60), None),
+        ]);
+
+        let challenge = state.curr_challenge.as_ref().unwrap();
+        assert!(!challenge.validator.finished());
+        assert!(challenge.validator.is_validated(KeyPos::C.oct(4)));
+        assert_eq!(challenge.wrong_attempts, 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn wrong_hand() {
+        // The treble note is played with the left hand, the bass note with the
+        // right one.
+        let state = play([
+            event(note_on(60), Some(Hand::Left)),
+            event(note_on(57), Some(Hand::Right)),
+        ]);
+
+        let challenge = state.curr_challenge.as_ref().unwrap();
+        assert!(!challenge.validator.is_validated(KeyPos::C.oct(4)));
+        assert_eq!(challenge.wrong_attempts, 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn unavailable_source() {
+        let (state, notices) = start(vec![
+            Box::new(UnavailableSource),
+            Box::new(FakeSource::new([
+                event(note_on(60), None),
+                event(note_on(57), None),
+            ])),
+        ]);
+
+        // The failure is reported, and the other sources are still played.
+        assert_eq!(notices.len(), 1);
+        assert!(state.curr_challenge.is_none());
+        assert!(state.prev_challenge.is_some());
+    }
+
+    #[wasm_bindgen_test]
+    fn device_disconnect() {
+        let sustain = |value: u8| MidiMessage::Controller {
+            controller: 64.into(),
+            value: value.into(),
+        };
+        let from = |device: &input::PortDescriptor, message| input::Event {
+            port: Some(device.port_id()),
+            ..event(message, None)
+        };
+
+        let (piano, pedals) = (port("piano"), port("pedals"));
+        let mut state = play([]);
+
+        for event in [
+            from(&piano, note_on(64)),
+            from(&pedals, sustain(127)),
+            from(&pedals, note_on(67)),
+        ] {
+            let _ = state.update(Message::Input(event));
+        }
+
+        let _ = state.update(Message::InputStatus {
+            port: piano.clone(),
+            status: input::Status::Disconnected,
+        });
+
+        // Only the keys and pedals of the disconnected device are released.
+        assert!(!state.pedals.contains_key(&Some(piano.port_id())));
+
+        let tracker = state.pedals.get_mut(&Some(pedals.port_id())).unwrap();
+        assert!(tracker.is_pressed(Pedal::Sustain));
+        assert_eq!(tracker.process(note_off(67)), []);
+        assert_eq!(tracker.process(sustain(0)), [
+            pedal::Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: false,
+            },
+            pedal::Event::Note(note_off(67)),
+        ]);
+    }
 }
@@ -1,5 +1,8 @@
 use {
-    crate::{keyboard::Key, verovio},
+    crate::{
+        keyboard::{Accidental, Key, KeySignature, Pitch},
+        verovio,
+    },
     derive_more::Display,
     smallvec::SmallVec,
     std::collections::HashMap,
@@ -17,21 +20,32 @@ pub struct Sheet {
     mode: Mode,
     clef_split: Key,
     notes: HashMap<Key, Note>,
+    treble_key_signature: KeySignature,
+    bass_key_signature: KeySignature,
 }
 
 impl Sheet {
-    pub fn new(mode: Mode, notes: &[Note], clef_split: Key) -> Self {
+    pub fn new(
+        mode: Mode,
+        notes: &[Note],
+        clef_split: Key,
+        treble_key_signature: KeySignature,
+        bass_key_signature: KeySignature,
+    ) -> Self {
         let notes = HashMap::from_iter(notes.iter().map(|note| (note.key, note.clone())));
 
         Self {
             mode,
             notes,
             clef_split,
+            treble_key_signature,
+            bass_key_signature,
         }
     }
 
     pub fn add_note(&mut self, key: Key, style: Style) {
-        self.notes.insert(key, Note::new(key, style));
+        let pitch = self.key_signature(key).spell_chromatic(key);
+        self.notes.insert(key, Note::new(key, pitch, style));
     }
 
     pub fn remove_note(&mut self, key: Key) {
@@ -44,13 +58,18 @@ impl Sheet {
         }
     }
 
-    pub fn render_hint_svg(&self) -> impl Future<Output = String> + use<> {
+    /// Renders the sheet as SVG, with the staff and notes drawn in the CSS
+    /// `color`.
+    pub fn render_hint_svg(
+        &self,
+        color: &str,
+    ) -> impl Future<Output = Result<String, verovio::Error>> + use<> {
         let treble_notes = self.treble_iter().collect::<SmallVec<[_; 4]>>();
         let (treble_notes, treble_styles) = if treble_notes.is_empty() {
             (generate_rest(), String::new())
         } else {
             (
-                render_notes_mei(&treble_notes),
+                render_notes_mei(&treble_notes, self.treble_key_signature),
                 render_note_styles(&treble_notes),
             )
         };
@@ -60,35 +79,50 @@ impl Sheet {
             (generate_rest(), String::new())
         } else {
             (
-                render_notes_mei(&bass_notes),
+                render_notes_mei(&bass_notes, self.bass_key_signature),
                 render_note_styles(&bass_notes),
             )
         };
 
         const TREBLE_NOTES_PAT: &str = "{{treble_notes}}";
         const BASS_NOTES_PAT: &str = "{{bass_notes}}";
+        const TREBLE_KEY_SIG_PAT: &str = "{{treble_key_sig}}";
+        const BASS_KEY_SIG_PAT: &str = "{{bass_key_sig}}";
+
+        let treble_key_sig = render_key_sig(self.treble_key_signature);
+        let bass_key_sig = render_key_sig(self.bass_key_signature);
 
         let mei = match self.mode {
-            Mode::Treble => include_str!("../../../resources/mei/treble.mei").replacen(
-                TREBLE_NOTES_PAT,
-                &treble_notes,
-                1,
-            ),
-
-            Mode::Bass => include_str!("../../../resources/mei/bass.mei").replacen(
-                BASS_NOTES_PAT,
-                &bass_notes,
-                1,
-            ),
+            Mode::Treble => include_str!("../../../resources/mei/treble.mei")
+                .replacen(TREBLE_KEY_SIG_PAT, &treble_key_sig, 1)
+                .replacen(TREBLE_NOTES_PAT, &treble_notes, 1),
+
+            Mode::Bass => include_str!("../../../resources/mei/bass.mei")
+                .replacen(BASS_KEY_SIG_PAT, &bass_key_sig, 1)
+                .replacen(BASS_NOTES_PAT, &bass_notes, 1),
 
             Mode::Combined => include_str!("../../../resources/mei/combined.mei")
+                .replacen(TREBLE_KEY_SIG_PAT, &treble_key_sig, 1)
+                .replacen(BASS_KEY_SIG_PAT, &bass_key_sig, 1)
                 .replacen(TREBLE_NOTES_PAT, &treble_notes, 1)
                 .replacen(BASS_NOTES_PAT, &bass_notes, 1),
         };
 
-        let styles = format!("{treble_styles} {bass_styles}");
+        let styles = format!("svg {{ color: {color}; }} {treble_styles} {bass_styles}");
 
-        async move { inject_styles(&verovio::convert_to_svg(mei).await, &styles) }
+        async move {
+            verovio::convert_to_svg(mei)
+                .await
+                .map(|svg| inject_styles(&svg, &styles))
+        }
+    }
+
+    fn key_signature(&self, key: Key) -> KeySignature {
+        if key >= self.clef_split {
+            self.treble_key_signature
+        } else {
+            self.bass_key_signature
+        }
     }
 
     fn treble_iter(&self) -> impl Iterator<Item = Note> {
@@ -133,18 +167,28 @@ pub enum Style {
 pub struct Note {
     pub id: Id,
     pub key: Key,
+    pub pitch: Pitch,
     pub style: Style,
 }
 
 impl Note {
-    fn new(key: Key, style: Style) -> Self {
+    fn new(key: Key, pitch: Pitch, style: Style) -> Self {
+        debug_assert_eq!(pitch.to_key().ok(), Some(key));
+
         Self {
             id: Id::generate(),
             key,
+            pitch,
             style,
         }
     }
 
+    /// Creates a note displayed with the specified spelling. The `pitch` is
+    /// expected to map onto the physical `key`.
+    pub fn spelled(key: Key, pitch: Pitch) -> Self {
+        Self::new(key, pitch, Style::default())
+    }
+
     fn render_style(&self) -> String {
         const ID_PAT: &str = "{{note-id}}";
         const STYLE_CORRECT: &str = include_str!("../../../resources/styles/note-correct.css");
@@ -160,17 +204,34 @@ impl Note {
 
 impl From<Key> for Note {
     fn from(key: Key) -> Self {
-        Self {
-            id: Id::generate(),
-            key,
-            style: Style::default(),
-        }
+        Self::new(key, key.into(), Style::default())
+    }
+}
+
+fn accid_value(accidental: Accidental) -> &'static str {
+    match accidental {
+        Accidental::DoubleFlat => "ff",
+        Accidental::Flat => "f",
+        Accidental::Natural => "n",
+        Accidental::Sharp => "s",
+        Accidental::DoubleSharp => "x",
     }
 }
 
-fn generate_accid_sharp() -> String {
+fn generate_accid(accidental: Accidental) -> String {
     let id = Id::generate();
-    format!("<accid xml:id=\"{id}\" accid=\"s\"/>")
+    let accid = accid_value(accidental);
+    format!("<accid xml:id=\"{id}\" accid=\"{accid}\"/>")
+}
+
+fn render_key_sig(key_signature: KeySignature) -> String {
+    let fifths = key_signature.fifths();
+
+    match fifths {
+        0 => "0".to_owned(),
+        1.. => format!("{fifths}s"),
+        _ => format!("{}f", fifths.unsigned_abs()),
+    }
 }
 
 fn generate_rest() -> String {
@@ -192,29 +253,41 @@ fn render_note_styles(notes: &[Note]) -> String {
         .join(" ")
 }
 
-fn render_notes_mei(notes: &[Note]) -> String {
+fn render_notes_mei(notes: &[Note], key_signature: KeySignature) -> String {
     if notes.len() == 1 {
-        render_note_mei(&notes[0])
+        render_note_mei(&notes[0], key_signature)
     } else {
         let id = Id::generate();
         let mut chord = format!("<chord xml:id=\"{id}\" dur=\"1\">");
         for note in notes {
-            chord.push_str(&render_note_mei(note));
+            chord.push_str(&render_note_mei(note, key_signature));
         }
         chord.push_str("</chord>");
         chord
     }
 }
 
-fn render_note_mei(note: &Note) -> String {
+fn render_note_mei(note: &Note, key_signature: KeySignature) -> String {
     let id = &note.id;
-    let pname = note.key.pos.pitch_name();
-    let oct = note.key.oct;
-    let inner = if note.key.pos.is_sharp() {
-        generate_accid_sharp()
+    let pname = note.pitch.letter.pitch_name();
+    let oct = note.pitch.oct;
+    let accidental = note.pitch.accidental;
+
+    // Accidentals implied by the key signature are not printed, but are still
+    // specified as gestural.
+    let (accid_ges, inner) = if accidental == key_signature.accidental(note.pitch.letter) {
+        let accid_ges = if accidental == Accidental::Natural {
+            String::new()
+        } else {
+            format!(" accid.ges=\"{}\"", accid_value(accidental))
+        };
+
+        (accid_ges, String::new())
     } else {
-        String::new()
+        (String::new(), generate_accid(accidental))
     };
 
-    format!("<note xml:id=\"{id}\" dur=\"1\" pname=\"{pname}\" oct=\"{oct}\">{inner}</note>")
+    format!(
+        "<note xml:id=\"{id}\" dur=\"1\" pname=\"{pname}\" oct=\"{oct}\"{accid_ges}>{inner}</note>"
+    )
 }
@@ -20,11 +20,13 @@ impl State {
         Task::none()
     }
 
+    pub fn play(&self) -> Message {
+        Message::StateTransition(StateTransition::GameActive(self.results.settings.clone()))
+    }
+
     pub fn view<'a>(&'a self, _: &'a App) -> Element<'a, Message> {
         widget::column![
-            widget::button("Play Again").on_press(Message::StateTransition(
-                StateTransition::GameActive(self.results.settings.clone())
-            )),
+            widget::button("Play Again").on_press(self.play()),
             widget::button("Main Menu")
                 .on_press(Message::StateTransition(StateTransition::MainMenu))
         ]
@@ -1,21 +1,45 @@
 use {
     super::{App, Message},
+    crate::{util::Hint as _, verovio},
     iced::{Element, Length, Subscription, Task, alignment, widget},
 };
 
 #[derive(Default)]
-pub struct State {}
+pub struct State {
+    error: Option<verovio::Error>,
+}
 
 impl State {
     pub fn init(&mut self) -> Task<Message> {
         Task::none()
     }
 
-    pub fn update(&mut self, _: Message) -> Task<Message> {
+    pub fn update(&mut self, event: Message) -> Task<Message> {
+        if let Message::LoadingFailed(err) = event {
+            tracing::error!(?err, "initialization failed");
+            self.error = Some(err);
+        }
+
         Task::none()
     }
 
     pub fn view<'a>(&'a self, _: &'a App) -> Element<'a, Message> {
+        if let Some(err) = &self.error {
+            return widget::column![
+                widget::vertical_space(),
+                widget::text("Failed to start the app").size(28),
+                widget::text(err.to_string()).style(widget::text::danger),
+                widget::text(err.hint()),
+                widget::button("Reload").on_press(Message::Reload),
+                widget::vertical_space(),
+            ]
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .spacing(20)
+            .align_x(alignment::Horizontal::Center)
+            .into();
+        }
+
         widget::column![
             widget::vertical_space(),
             widget::text("Initializing..."),
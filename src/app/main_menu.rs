@@ -1,39 +1,155 @@
 use {
-    super::{App, Config, Message, OctaveRange, StateTransition},
+    super::{
+        App,
+        Config,
+        HintAttempts,
+        HintDelay,
+        Message,
+        OctaveRange,
+        PedalAction,
+        SoundConfig,
+        StateTransition,
+        ThemeSetting,
+    },
     crate::{
-        app::{self, Clef, Font},
-        input,
+        app::{self, Clef, Font, notification::Notice},
+        input::{
+            self,
+            channel::{Channel, Channels},
+            output,
+            pedal::Pedal,
+            qwerty::{self, Mapping},
+        },
+        keyboard::{Accidental, Key, KeySignature, Keyboard, KeyboardSize, Letter, PitchClass},
+        naming::{Naming, NoteNames, OctaveNotation},
+        scale::{Scale, ScaleKind},
     },
     iced::{Element, Length, Subscription, Task, alignment, widget},
 };
 
 pub struct State {
     input_devices: Vec<input::Device>,
+    output_devices: Vec<output::Device>,
+    natural_keys: Vec<Key>,
+    tonics: Vec<PitchClass>,
     config: Config,
 }
 
 impl State {
     pub fn new() -> Self {
-        let mut state = Self {
+        Self {
             input_devices: Vec::new(),
+            output_devices: Vec::new(),
+            natural_keys: Keyboard::standard_88_key().iter_natural_keys().collect(),
+            tonics: Letter::ALL
+                .into_iter()
+                .flat_map(|letter| {
+                    [Accidental::Flat, Accidental::Natural, Accidental::Sharp]
+                        .map(|accidental| PitchClass::new(letter, accidental))
+                })
+                .collect(),
             config: Config::load(),
-        };
-        state.update_input_devices();
-        state
+        }
     }
 
     pub fn init(&mut self) -> Task<Message> {
-        Task::none()
+        self.update_input_devices()
     }
 
     pub fn update(&mut self, event: Message) -> Task<Message> {
         match event {
             Message::RefreshDeviceList => {
-                self.update_input_devices();
+                return self.update_input_devices();
+            }
+
+            Message::ToggleInputDevice(device, enabled) => {
+                let devices = &mut self.config.input_devices;
+
+                match devices
+                    .iter_mut()
+                    .find(|selection| selection.device == device)
+                {
+                    Some(selection) => selection.enabled = enabled,
+                    None => devices.push(input::Selection {
+                        device,
+                        enabled,
+                        channels: Default::default(),
+                    }),
+                }
+
+                // Forget the disabled devices which are no longer plugged in.
+                let available = &self.input_devices;
+                devices
+                    .retain(|selection| selection.enabled || available.contains(&selection.device));
+
+                self.config.store();
+            }
+
+            Message::SelectQwertyMapping(mapping) => {
+                self.config.qwerty = mapping;
+                self.config.store();
+            }
+
+            Message::SelectOutput(output) => {
+                self.config.output = output;
+                self.config.store();
+            }
+
+            Message::SelectSound(sound) => {
+                self.config.sound = sound;
+                self.config.store();
+            }
+
+            Message::SelectChannels { port, channels } => {
+                let device = input::Device::Midi(port);
+
+                if let Some(selection) = self
+                    .config
+                    .input_devices
+                    .iter_mut()
+                    .find(|selection| selection.device == device)
+                {
+                    selection.channels = channels;
+                    self.config.store();
+                }
+            }
+
+            Message::SelectKeyboard(keyboard) => {
+                self.config.keyboard = keyboard;
+                self.config.store();
+            }
+
+            Message::SelectKeyboardRange { first, last } => {
+                let range = first.to_midi().as_int()..=last.to_midi().as_int();
+
+                if let Ok(keyboard) = Keyboard::new(range) {
+                    self.config.keyboard = KeyboardSize::Custom(keyboard);
+                    self.config.store();
+                }
+            }
+
+            Message::SelectNaming(naming) => {
+                self.config.naming = naming;
+                self.config.store();
+            }
+
+            Message::SelectTheme(theme) => {
+                self.config.theme = theme;
+                self.config.store();
             }
 
-            Message::SelectInputPort(port) => {
-                self.config.input_device = port;
+            Message::SelectHintDelay(delay) => {
+                self.config.hints.delay = delay;
+                self.config.store();
+            }
+
+            Message::SelectHintAttempts(attempts) => {
+                self.config.hints.attempts = attempts;
+                self.config.store();
+            }
+
+            Message::SelectPedalAction { pedal, action } => {
+                self.config.pedals.set(pedal, action);
                 self.config.store();
             }
 
@@ -53,15 +169,43 @@ impl State {
                 self.config.store();
             }
 
+            Message::SelectKeySignature {
+                clef,
+                key_signature,
+            } => {
+                match clef {
+                    Clef::Treble => self.config.treble.key_signature = key_signature,
+                    Clef::Bass => self.config.bass.key_signature = key_signature,
+                }
+                self.config.store();
+            }
+
+            Message::SelectScale { clef, scale } => {
+                let config = match clef {
+                    Clef::Treble => &mut self.config.treble,
+                    Clef::Bass => &mut self.config.bass,
+                };
+
+                // Use the conventional key signature for the scale by default. Scales
+                // without one are written with accidentals in C major.
+                if let Some(scale) = scale {
+                    config.key_signature = scale.key_signature().unwrap_or_default();
+                }
+
+                config.scale = scale;
+                self.config.store();
+            }
+
             _ => {}
         }
 
         Task::none()
     }
 
-    pub fn view<'a>(&'a self, _: &'a App) -> Element<'a, Message> {
+    pub fn view<'a>(&'a self, app: &'a App) -> Element<'a, Message> {
         let col_width = Length::Fixed(250.);
         let spacing = 20.;
+        let naming = self.config.naming;
 
         let title = {
             let label = widget::text(app::TITLE)
@@ -80,21 +224,458 @@ impl State {
             .spacing(spacing)
         };
 
+        let channel_config =
+            |port: &'a input::PortDescriptor, channels: Channels| -> Element<'a, Message> {
+                let label = widget::text(format!("{port}:"))
+                    .width(col_width)
+                    .align_x(alignment::Horizontal::Right);
+
+                let select = move |channels| Message::SelectChannels {
+                    port: port.clone(),
+                    channels,
+                };
+
+                let filter_selector =
+                    widget::pick_list(&Channel::ALL[..], channels.filter, move |channel| {
+                        select(Channels {
+                            filter: Some(channel),
+                            ..channels
+                        })
+                    })
+                    .placeholder("All channels")
+                    .width(Length::Fixed(150.));
+
+                let right_selector =
+                    widget::pick_list(&Channel::ALL[..], channels.right_hand, move |channel| {
+                        select(Channels {
+                            right_hand: Some(channel),
+                            ..channels
+                        })
+                    })
+                    .placeholder("None")
+                    .width(Length::Fixed(100.));
+
+                let left_selector =
+                    widget::pick_list(&Channel::ALL[..], channels.left_hand, move |channel| {
+                        select(Channels {
+                            left_hand: Some(channel),
+                            ..channels
+                        })
+                    })
+                    .placeholder("None")
+                    .width(Length::Fixed(100.));
+
+                let btn_clear = widget::button("Clear").on_press_maybe(
+                    (channels != Channels::default()).then(|| select(Channels::default())),
+                );
+
+                widget::row![
+                    label,
+                    filter_selector,
+                    widget::text("Right hand:"),
+                    right_selector,
+                    widget::text("Left hand:"),
+                    left_selector,
+                    btn_clear,
+                ]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+                .into()
+            };
+
+        let qwerty_config = |mapping: &Mapping| {
+            let label = widget::text("Computer keyboard:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let select = |mapping: Mapping| Message::SelectQwertyMapping(mapping);
+
+            // Only the last typed character is kept, so typing replaces the key.
+            let char_input = |value: char, update: fn(&mut Mapping, char)| {
+                let mapping = mapping.clone();
+
+                widget::text_input("", &value.to_string())
+                    .on_input(move |input| {
+                        let mut mapping = mapping.clone();
+
+                        if let Some(ch) = input.chars().last() {
+                            update(&mut mapping, ch.to_ascii_lowercase());
+                        }
+
+                        select(mapping)
+                    })
+                    .width(Length::Fixed(40.))
+            };
+
+            let keys_input = {
+                let mapping = mapping.clone();
+
+                widget::text_input("Keys from C", &mapping.keys)
+                    .on_input(move |keys| {
+                        select(Mapping {
+                            keys: keys.to_lowercase(),
+                            ..mapping.clone()
+                        })
+                    })
+                    .width(col_width)
+            };
+
+            let octave_down = char_input(mapping.octave_down, |mapping, ch| {
+                mapping.octave_down = ch;
+            });
+
+            let octave_up = char_input(mapping.octave_up, |mapping, ch| mapping.octave_up = ch);
+
+            let octave_selector = {
+                let mapping = mapping.clone();
+
+                widget::pick_list(
+                    (0..=qwerty::MAX_OCTAVE).collect::<Vec<_>>(),
+                    Some(mapping.octave),
+                    move |octave| {
+                        select(Mapping {
+                            octave,
+                            ..mapping.clone()
+                        })
+                    },
+                )
+                .width(Length::Fixed(60.))
+            };
+
+            let btn_reset = widget::button("Reset").on_press_maybe(
+                (*mapping != Mapping::default()).then(|| select(Mapping::default())),
+            );
+
+            widget::row![
+                label,
+                keys_input,
+                widget::text("Octave:"),
+                octave_selector,
+                widget::text("Down:"),
+                octave_down,
+                widget::text("Up:"),
+                octave_up,
+                btn_reset,
+            ]
+            .width(Length::Fill)
+            .align_y(alignment::Vertical::Center)
+            .spacing(spacing)
+        };
+
         let device = {
-            let label = widget::text("Input device:")
+            let label = widget::text("Input devices:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            // The replay device is available once a midi file is opened.
+            let mut available = self.input_devices.clone();
+
+            if app.replay.is_some() {
+                available.push(input::Device::Replay);
+            }
+
+            // Selected devices which are not plugged in are still listed, so they can
+            // be disabled.
+            let unavailable = self
+                .config
+                .input_devices
+                .iter()
+                .map(|selection| &selection.device)
+                .filter(|device| !available.contains(device));
+
+            let checkboxes = available
+                .iter()
+                .chain(unavailable)
+                .map(|device| {
+                    let label = match (device, &app.replay) {
+                        (input::Device::Replay, Some(recording)) => {
+                            format!("{device} ({})", recording.name)
+                        }
+                        _ if available.contains(device) => device.to_string(),
+                        _ => format!("{device} (not connected)"),
+                    };
+
+                    let toggled = device.clone();
+
+                    widget::checkbox(label, self.config.is_device_enabled(device))
+                        .on_toggle(move |enabled| {
+                            Message::ToggleInputDevice(toggled.clone(), enabled)
+                        })
+                        .into()
+                })
+                .collect::<Vec<_>>();
+
+            let selector = widget::Column::with_children(checkboxes)
+                .spacing(10)
+                .width(col_width);
+
+            let btn_refresh = widget::button("Refresh").on_press(Message::RefreshDeviceList);
+            let btn_replay = widget::button("Open MIDI File...").on_press(Message::OpenReplay);
+
+            let device = widget::row![label, selector, btn_refresh, btn_replay]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing);
+
+            let channels = self
+                .config
+                .input_devices
+                .iter()
+                .filter(|selection| selection.enabled)
+                .filter_map(|selection| match &selection.device {
+                    input::Device::Midi(port) => Some(channel_config(port, selection.channels)),
+                    _ => None,
+                });
+
+            let qwerty = self
+                .config
+                .is_device_enabled(&input::Device::Qwerty)
+                .then(|| qwerty_config(&self.config.qwerty));
+
+            widget::column![device]
+                .extend(channels)
+                .push_maybe(qwerty)
+                .spacing(spacing)
+        };
+
+        let output = {
+            let label = widget::text("Output device:")
                 .width(col_width)
                 .align_x(alignment::Horizontal::Right);
 
+            let config = &self.config.output;
+
             let selector = widget::pick_list(
-                &self.input_devices[..],
-                Some(self.config.input_device.clone()),
-                Message::SelectInputPort,
+                &self.output_devices[..],
+                Some(config.device.clone()),
+                move |device| {
+                    Message::SelectOutput(output::Config {
+                        device,
+                        ..config.clone()
+                    })
+                },
             )
             .width(col_width);
 
-            let btn_refresh = widget::button("Refresh").on_press(Message::RefreshDeviceList);
+            let lights =
+                widget::checkbox("Light up keys", config.lights).on_toggle(move |lights| {
+                    Message::SelectOutput(output::Config {
+                        lights,
+                        ..config.clone()
+                    })
+                });
 
-            widget::row![label, selector, btn_refresh]
+            let light_channel = widget::pick_list(
+                &Channel::ALL[..],
+                Some(config.light_channel),
+                move |light_channel| {
+                    Message::SelectOutput(output::Config {
+                        light_channel,
+                        ..config.clone()
+                    })
+                },
+            )
+            .width(Length::Fixed(100.));
+
+            let sounds =
+                widget::checkbox("Feedback sounds", config.sounds).on_toggle(move |sounds| {
+                    Message::SelectOutput(output::Config {
+                        sounds,
+                        ..config.clone()
+                    })
+                });
+
+            let feedback_channel = widget::pick_list(
+                &Channel::ALL[..],
+                Some(config.feedback_channel),
+                move |feedback_channel| {
+                    Message::SelectOutput(output::Config {
+                        feedback_channel,
+                        ..config.clone()
+                    })
+                },
+            )
+            .width(Length::Fixed(100.));
+
+            widget::row![
+                label,
+                selector,
+                lights,
+                light_channel,
+                sounds,
+                feedback_channel
+            ]
+            .width(Length::Fill)
+            .align_y(alignment::Vertical::Center)
+            .spacing(spacing)
+        };
+
+        let sound = {
+            let label = widget::text("Sound:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let config = self.config.sound;
+
+            let notes = widget::checkbox("Play notes", config.notes)
+                .on_toggle(move |notes| Message::SelectSound(SoundConfig { notes, ..config }))
+                .width(col_width);
+
+            let effects = widget::checkbox("Click and error sounds", config.effects)
+                .on_toggle(move |effects| Message::SelectSound(SoundConfig { effects, ..config }))
+                .width(col_width);
+
+            widget::row![label, notes, effects]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+        };
+
+        let keyboard = {
+            let label = widget::text("Keyboard:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let current = self.config.keyboard.to_keyboard();
+            let sizes = KeyboardSize::PRESETS
+                .into_iter()
+                .chain([KeyboardSize::Custom(current.clone())])
+                .collect::<Vec<_>>();
+
+            let selector = widget::pick_list(
+                sizes,
+                Some(self.config.keyboard.clone()),
+                Message::SelectKeyboard,
+            )
+            .width(col_width);
+
+            let (first, last) = (current.first(), current.last());
+
+            let range_selectors = self.config.keyboard.is_custom().then(|| {
+                let keys = self
+                    .natural_keys
+                    .iter()
+                    .map(|key| naming.named(*key))
+                    .collect::<Vec<_>>();
+
+                widget::row![
+                    widget::pick_list(keys.clone(), Some(naming.named(first)), move |first| {
+                        Message::SelectKeyboardRange {
+                            first: first.value,
+                            last,
+                        }
+                    })
+                    .width(col_width),
+                    widget::pick_list(keys, Some(naming.named(last)), move |last| {
+                        Message::SelectKeyboardRange {
+                            first,
+                            last: last.value,
+                        }
+                    })
+                    .width(col_width),
+                ]
+                .spacing(spacing)
+            });
+
+            widget::row![label, selector]
+                .push_maybe(range_selectors)
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+        };
+
+        let note_names = {
+            let label = widget::text("Note names:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let current = naming;
+
+            let names_selector =
+                widget::pick_list(&NoteNames::ALL[..], Some(current.names), move |names| {
+                    Message::SelectNaming(Naming { names, ..current })
+                })
+                .width(col_width);
+
+            let octaves_selector = widget::pick_list(
+                &OctaveNotation::ALL[..],
+                Some(current.octaves),
+                move |octaves| Message::SelectNaming(Naming { octaves, ..current }),
+            )
+            .width(col_width);
+
+            widget::row![label, names_selector, octaves_selector]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+        };
+
+        let theme = {
+            let label = widget::text("Theme:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let selector = widget::pick_list(
+                &ThemeSetting::ALL[..],
+                Some(self.config.theme),
+                Message::SelectTheme,
+            )
+            .width(col_width);
+
+            widget::row![label, selector]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+        };
+
+        let hints = {
+            let label = widget::text("Reveal keys:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let delay_selector = widget::pick_list(
+                &HintDelay::ALL[..],
+                Some(self.config.hints.delay),
+                Message::SelectHintDelay,
+            )
+            .width(col_width);
+
+            let attempts_selector = widget::pick_list(
+                &HintAttempts::ALL[..],
+                Some(self.config.hints.attempts),
+                Message::SelectHintAttempts,
+            )
+            .width(col_width);
+
+            widget::row![label, delay_selector, attempts_selector]
+                .width(Length::Fill)
+                .align_y(alignment::Vertical::Center)
+                .spacing(spacing)
+        };
+
+        let pedals = {
+            let label = widget::text("Pedal actions:")
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let selectors = Pedal::ALL.into_iter().map(|pedal| {
+                widget::row![
+                    widget::text(format!("{pedal}:")),
+                    widget::pick_list(
+                        &PedalAction::ALL[..],
+                        Some(self.config.pedals.get(pedal)),
+                        move |action| Message::SelectPedalAction { pedal, action },
+                    )
+                    .width(Length::Fixed(150.)),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center)
+                .into()
+            });
+
+            widget::row![label]
+                .extend(selectors)
                 .width(Length::Fill)
                 .align_y(alignment::Vertical::Center)
                 .spacing(spacing)
@@ -108,40 +689,110 @@ impl State {
             OctaveRange::All,
         ];
 
-        let clef_config = |clef, label, selected, include_sharp_keys| {
+        let clef_config = |clef, label, config: &app::ClefConfig| {
             let label = widget::text(label)
                 .width(col_width)
                 .align_x(alignment::Horizontal::Right);
 
             let octave_selector =
-                widget::pick_list(OCTAVE_SELECTION, Some(selected), move |range| {
+                widget::pick_list(OCTAVE_SELECTION, Some(config.range), move |range| {
                     Message::SelectOctaveRange { clef, range }
                 })
                 .width(col_width);
 
-            let sharp_keys_toggle = widget::checkbox("Include sharp keys", include_sharp_keys)
-                .on_toggle(move |enabled| Message::ToggleSharpKeys { clef, enabled })
+            let key_signature_selector = widget::pick_list(
+                KeySignature::ALL.map(|key_signature| naming.named(key_signature)),
+                Some(naming.named(config.key_signature)),
+                move |key_signature| Message::SelectKeySignature {
+                    clef,
+                    key_signature: key_signature.value,
+                },
+            )
+            .width(col_width);
+
+            // Scales determine the keys on their own, so the toggle has no effect
+            // while one is selected.
+            let sharp_keys_toggle = widget::checkbox("Include accidentals", config.sharp_keys)
+                .on_toggle_maybe(
+                    config
+                        .scale
+                        .is_none()
+                        .then_some(move |enabled| Message::ToggleSharpKeys { clef, enabled }),
+                )
                 .width(col_width);
 
-            widget::row![label, octave_selector, sharp_keys_toggle]
+            widget::row![
+                label,
+                octave_selector,
+                key_signature_selector,
+                sharp_keys_toggle
+            ]
+            .width(Length::Fill)
+            .align_y(alignment::Vertical::Center)
+            .spacing(spacing)
+        };
+
+        let scale_config = |clef, label, config: &app::ClefConfig| {
+            let label = widget::text(label)
+                .width(col_width)
+                .align_x(alignment::Horizontal::Right);
+
+            let scale = config.scale;
+
+            let tonics = self
+                .tonics
+                .iter()
+                .map(|tonic| naming.named(*tonic))
+                .collect::<Vec<_>>();
+
+            let tonic_selector = widget::pick_list(
+                tonics,
+                scale.map(|scale| naming.named(scale.tonic)),
+                move |tonic| {
+                    let tonic = tonic.value;
+                    let kind = scale.map_or(ScaleKind::Major, |scale| scale.kind);
+                    Message::SelectScale {
+                        clef,
+                        scale: Some(Scale::new(tonic, kind)),
+                    }
+                },
+            )
+            .placeholder("Any tonic")
+            .width(col_width);
+
+            let kind_selector = widget::pick_list(
+                &ScaleKind::ALL[..],
+                scale.map(|scale| scale.kind),
+                move |kind| {
+                    let tonic = scale
+                        .map_or(PitchClass::new(Letter::C, Accidental::Natural), |scale| {
+                            scale.tonic
+                        });
+                    Message::SelectScale {
+                        clef,
+                        scale: Some(Scale::new(tonic, kind)),
+                    }
+                },
+            )
+            .placeholder("Any scale")
+            .width(col_width);
+
+            let btn_clear = widget::button("Clear").on_press_maybe(
+                scale
+                    .is_some()
+                    .then_some(Message::SelectScale { clef, scale: None }),
+            );
+
+            widget::row![label, tonic_selector, kind_selector, btn_clear]
                 .width(Length::Fill)
                 .align_y(alignment::Vertical::Center)
                 .spacing(spacing)
         };
 
-        let treble_config = clef_config(
-            Clef::Treble,
-            "Treble octaves:",
-            self.config.treble.range,
-            self.config.treble.sharp_keys,
-        );
-
-        let bass_config = clef_config(
-            Clef::Bass,
-            "Bass octaves:",
-            self.config.bass.range,
-            self.config.bass.sharp_keys,
-        );
+        let treble_config = clef_config(Clef::Treble, "Treble octaves:", &self.config.treble);
+        let treble_scale = scale_config(Clef::Treble, "Treble scale:", &self.config.treble);
+        let bass_config = clef_config(Clef::Bass, "Bass octaves:", &self.config.bass);
+        let bass_scale = scale_config(Clef::Bass, "Bass scale:", &self.config.bass);
 
         let btn_play = {
             let label = widget::text("Play")
@@ -149,13 +800,8 @@ impl State {
                 .width(Length::Fill)
                 .align_x(alignment::Horizontal::Center);
 
-            let is_form_valid =
-                !(self.config.treble.range.is_none() && self.config.bass.range.is_none());
-
             let btn = widget::button(label)
-                .on_press_maybe(is_form_valid.then(|| {
-                    Message::StateTransition(StateTransition::GameActive(self.config.clone()))
-                }))
+                .on_press_maybe(self.play())
                 .width(col_width);
 
             widget::row![widget::horizontal_space(), btn, widget::horizontal_space()]
@@ -164,16 +810,31 @@ impl State {
                 .spacing(spacing)
         };
 
+        let shortcuts_hint = widget::text("Press ? for keyboard shortcuts")
+            .size(14)
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Center);
+
         let col = widget::column![
             widget::vertical_space().height(Length::FillPortion(1)),
             title,
             device,
+            output,
+            sound,
+            keyboard,
+            note_names,
+            theme,
+            hints,
+            pedals,
             treble_config,
+            treble_scale,
             bass_config,
+            bass_scale,
             btn_play,
+            shortcuts_hint,
             widget::vertical_space().height(Length::FillPortion(3)),
         ]
-        .width(Length::Fixed(790.))
+        .width(Length::Fixed(1060.))
         .height(Length::Fill)
         .spacing(spacing);
 
@@ -183,13 +844,43 @@ impl State {
             .into()
     }
 
+    /// Returns the message starting the game, if the configuration is valid.
+    pub fn play(&self) -> Option<Message> {
+        let keyboard = self.config.keyboard.to_keyboard();
+        let is_form_valid = self.config.treble.to_key_range(&keyboard).is_some()
+            || self.config.bass.to_key_range(&keyboard).is_some();
+
+        is_form_valid
+            .then(|| Message::StateTransition(StateTransition::GameActive(self.config.clone())))
+    }
+
     pub fn subscription<'a>(&'a self, _: &'a App) -> Subscription<Message> {
         Subscription::none()
     }
 
-    fn update_input_devices(&mut self) {
-        self.input_devices = vec![input::Device::Virtual];
-        self.input_devices
-            .extend(input::port_list().into_iter().map(input::Device::Midi));
+    fn update_input_devices(&mut self) -> Task<Message> {
+        // The on-screen keyboard is always available, so it's not listed.
+        self.input_devices = vec![input::Device::Qwerty];
+
+        // Errors are reported for the input ports already.
+        self.output_devices = [output::Device::None]
+            .into_iter()
+            .chain(
+                input::output_port_list()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(output::Device::Midi),
+            )
+            .collect();
+
+        match input::port_list() {
+            Ok(ports) => {
+                self.input_devices
+                    .extend(ports.into_iter().map(input::Device::Midi));
+                Task::none()
+            }
+
+            Err(err) => Task::done(Message::Notify(Notice::from_error(&err))),
+        }
     }
 }
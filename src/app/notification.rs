@@ -0,0 +1,127 @@
+use {
+    super::Message,
+    crate::util::{self, Hint},
+    iced::{Color, Element, Length, Task, alignment, widget},
+};
+
+/// Time after which notifications are dismissed automatically, in milliseconds.
+const TIMEOUT: i32 = 8000;
+const MAX_VISIBLE: usize = 4;
+const WARNING_COLOR: Color = Color::from_rgb(0.9, 0.6, 0.1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub level: Level,
+    pub text: String,
+}
+
+impl Notice {
+    pub fn info(text: impl Into<String>) -> Self {
+        Self {
+            level: Level::Info,
+            text: text.into(),
+        }
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warning,
+            text: text.into(),
+        }
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self {
+            level: Level::Error,
+            text: text.into(),
+        }
+    }
+
+    /// Error notice with the message and the hint of the error.
+    pub fn from_error(err: &impl Hint) -> Self {
+        Self::error(err.describe())
+    }
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    items: Vec<(u64, Notice)>,
+    last_id: u64,
+}
+
+impl Notifications {
+    /// Shows the notice and schedules its dismissal.
+    pub fn push(&mut self, notice: Notice) -> Task<Message> {
+        match notice.level {
+            Level::Info => tracing::info!(text = notice.text, "notification"),
+            Level::Warning | Level::Error => tracing::warn!(text = notice.text, "notification"),
+        }
+
+        self.last_id += 1;
+        let id = self.last_id;
+
+        self.items.push((id, notice));
+
+        if self.items.len() > MAX_VISIBLE {
+            self.items.remove(0);
+        }
+
+        Task::future(async move {
+            util::sleep(TIMEOUT).await;
+            Message::DismissNotification(id)
+        })
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.items.retain(|(item_id, _)| *item_id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let toasts = self.items.iter().map(|(id, notice)| {
+            let text = widget::text(notice.text.as_str()).width(Length::Fill);
+
+            let text = match notice.level {
+                Level::Info => text,
+                Level::Warning => text.color(WARNING_COLOR),
+                Level::Error => text.style(widget::text::danger),
+            };
+
+            let btn_dismiss = widget::button("Dismiss")
+                .style(widget::button::text)
+                .on_press(Message::DismissNotification(*id));
+
+            widget::container(
+                widget::row![text, btn_dismiss]
+                    .spacing(10)
+                    .align_y(alignment::Vertical::Center),
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(widget::container::rounded_box)
+            .into()
+        });
+
+        widget::container(
+            widget::Column::with_children(toasts)
+                .spacing(10)
+                .width(Length::Fixed(450.)),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(alignment::Horizontal::Right)
+        .align_y(alignment::Vertical::Bottom)
+        .into()
+    }
+}
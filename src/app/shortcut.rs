@@ -0,0 +1,135 @@
+use {
+    super::Message,
+    derive_more::Display,
+    iced::{
+        Element,
+        Length,
+        alignment,
+        event,
+        keyboard::{self, Key, Modifiers, key},
+        widget,
+        window,
+    },
+};
+
+/// Keyboard shortcuts. Letter keys are only used with the `Alt` modifier, so
+/// they don't interfere with the computer keyboard input device.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    #[display("Play")]
+    Play,
+
+    #[display("Skip challenge")]
+    Skip,
+
+    #[display("Toggle on-screen keyboard")]
+    ToggleKeyboard,
+
+    #[display("Toggle fullscreen")]
+    ToggleFullscreen,
+
+    #[display("Main menu")]
+    MainMenu,
+
+    #[display("Show or hide this help")]
+    Help,
+}
+
+impl Shortcut {
+    pub const ALL: [Self; 6] = [
+        Self::Play,
+        Self::Skip,
+        Self::ToggleKeyboard,
+        Self::ToggleFullscreen,
+        Self::MainMenu,
+        Self::Help,
+    ];
+
+    pub fn keys(&self) -> &'static str {
+        match self {
+            Self::Play => "Enter",
+            Self::Skip => "Space",
+            Self::ToggleKeyboard => "Alt + K",
+            Self::ToggleFullscreen => "Alt + F",
+            Self::MainMenu => "Escape",
+            Self::Help => "?",
+        }
+    }
+
+    fn from_key(key: Key<&str>, physical_key: key::Physical, modifiers: Modifiers) -> Option<Self> {
+        match key {
+            Key::Named(key::Named::Enter) => Some(Self::Play),
+            Key::Named(key::Named::Space) => Some(Self::Skip),
+            Key::Named(key::Named::Escape) => Some(Self::MainMenu),
+            Key::Character("?") => Some(Self::Help),
+
+            // Physical keys are used, since `Alt` changes the produced character on
+            // some platforms.
+            _ if modifiers.alt() => match physical_key {
+                key::Physical::Code(key::Code::KeyK) => Some(Self::ToggleKeyboard),
+                key::Physical::Code(key::Code::KeyF) => Some(Self::ToggleFullscreen),
+                _ => None,
+            },
+
+            _ => None,
+        }
+    }
+}
+
+/// Subscription listener for keyboard shortcuts, ignoring events already
+/// captured by other widgets.
+pub fn listen(event: iced::Event, status: event::Status, _: window::Id) -> Option<Message> {
+    if status == event::Status::Captured {
+        return None;
+    }
+
+    match event {
+        iced::Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            physical_key,
+            modifiers,
+            ..
+        }) => Shortcut::from_key(key.as_ref(), physical_key, modifiers).map(Message::Shortcut),
+
+        _ => None,
+    }
+}
+
+/// Overlay listing the keyboard shortcuts.
+pub fn help<'a>() -> Element<'a, Message> {
+    let rows = Shortcut::ALL.into_iter().map(|shortcut| {
+        widget::row![
+            widget::text(shortcut.keys())
+                .width(Length::Fixed(100.))
+                .align_x(alignment::Horizontal::Right),
+            widget::text(shortcut.to_string()),
+        ]
+        .spacing(20)
+        .into()
+    });
+
+    let content = widget::column![widget::text("Keyboard Shortcuts").size(24)]
+        .extend(rows)
+        .push(widget::button("Close").on_press(Message::Shortcut(Shortcut::Help)))
+        .spacing(10)
+        .align_x(alignment::Horizontal::Center);
+
+    let dialog = widget::container(content)
+        .padding(20)
+        .style(widget::container::rounded_box);
+
+    widget::opaque(widget::center(dialog).style(|theme: &iced::Theme| {
+        widget::container::Style {
+            background: Some(
+                theme
+                    .extended_palette()
+                    .background
+                    .base
+                    .color
+                    .scale_alpha(0.8)
+                    .into(),
+            ),
+            ..Default::default()
+        }
+    }))
+}
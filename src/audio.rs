@@ -0,0 +1,164 @@
+use {
+    crate::{
+        synth::{self, Effect},
+        util,
+    },
+    midly::num::u7,
+    std::collections::HashMap,
+    tap::TapFallible as _,
+    web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, GainNode},
+};
+
+#[derive(Debug, thiserror::Error, Clone)]
+pub enum Error {
+    #[error("Failed to initialize audio: {0}")]
+    InitFailed(String),
+
+    #[error("Failed to play sound: {0}")]
+    PlaybackFailed(String),
+}
+
+/// Notes of each layer are separate voices, so that e.g. the played answer
+/// doesn't cut off the same note played by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Notes played on the input devices.
+    Input,
+    /// Notes of the challenge answer.
+    Answer,
+}
+
+struct Voice {
+    source: AudioBufferSourceNode,
+    gain: GainNode,
+}
+
+/// Plays the sounds rendered by the synthesizer through Web Audio.
+pub struct Audio {
+    ctx: AudioContext,
+    /// Rendered sounds, reused for subsequent plays.
+    notes: HashMap<u7, AudioBuffer>,
+    effects: HashMap<Effect, AudioBuffer>,
+    /// Notes currently playing.
+    voices: HashMap<(Layer, u7), Voice>,
+}
+
+impl Audio {
+    pub fn new() -> Result<Self, Error> {
+        let ctx =
+            AudioContext::new().map_err(|err| Error::InitFailed(util::js_error_to_string(err)))?;
+
+        Ok(Self {
+            ctx,
+            notes: HashMap::new(),
+            effects: HashMap::new(),
+            voices: HashMap::new(),
+        })
+    }
+
+    pub fn note_on(&mut self, layer: Layer, key: u7, vel: u7) {
+        let _ = self
+            .try_note_on(layer, key, vel)
+            .tap_err(|err| tracing::warn!(?err, "failed to play note"));
+    }
+
+    pub fn note_off(&mut self, layer: Layer, key: u7) {
+        let Some(voice) = self.voices.remove(&(layer, key)) else {
+            return;
+        };
+
+        let now = self.ctx.current_time();
+
+        let _ = voice
+            .gain
+            .gain()
+            .set_target_at_time(0., now, synth::RELEASE)
+            .and_then(|_| voice.source.stop_with_when(now + synth::RELEASE * 5.))
+            .tap_err(|err| tracing::warn!(?err, "failed to release note"));
+    }
+
+    pub fn play_effect(&mut self, effect: Effect) {
+        let _ = self
+            .try_play_effect(effect)
+            .tap_err(|err| tracing::warn!(?err, "failed to play effect"));
+    }
+
+    fn try_note_on(&mut self, layer: Layer, key: u7, vel: u7) -> Result<(), Error> {
+        self.note_off(layer, key);
+
+        let buffer = match self.notes.get(&key) {
+            Some(buffer) => buffer.clone(),
+            None => {
+                let buffer =
+                    self.create_buffer(&synth::render_note(key, self.ctx.sample_rate()))?;
+                self.notes.insert(key, buffer.clone());
+                buffer
+            }
+        };
+
+        let voice = self.play_buffer(&buffer, synth::velocity_gain(vel))?;
+        self.voices.insert((layer, key), voice);
+
+        Ok(())
+    }
+
+    fn try_play_effect(&mut self, effect: Effect) -> Result<(), Error> {
+        let buffer = match self.effects.get(&effect) {
+            Some(buffer) => buffer.clone(),
+            None => {
+                let buffer =
+                    self.create_buffer(&synth::render_effect(effect, self.ctx.sample_rate()))?;
+                self.effects.insert(effect, buffer.clone());
+                buffer
+            }
+        };
+
+        self.play_buffer(&buffer, 1.)?;
+
+        Ok(())
+    }
+
+    fn create_buffer(&self, samples: &[f32]) -> Result<AudioBuffer, Error> {
+        let buffer = self
+            .ctx
+            .create_buffer(1, samples.len() as u32, self.ctx.sample_rate())
+            .map_err(playback_error)?;
+
+        buffer.copy_to_channel(samples, 0).map_err(playback_error)?;
+
+        Ok(buffer)
+    }
+
+    fn play_buffer(&self, buffer: &AudioBuffer, gain: f32) -> Result<Voice, Error> {
+        // Browsers keep the context suspended until the user interacts with the
+        // page, which is the case once a sound is played.
+        let _ = self.ctx.resume();
+
+        let source = self.ctx.create_buffer_source().map_err(playback_error)?;
+        source.set_buffer(Some(buffer));
+
+        let gain_node = self.ctx.create_gain().map_err(playback_error)?;
+        gain_node.gain().set_value(gain);
+
+        source
+            .connect_with_audio_node(&gain_node)
+            .and_then(|_| gain_node.connect_with_audio_node(&self.ctx.destination()))
+            .and_then(|_| source.start())
+            .map_err(playback_error)?;
+
+        Ok(Voice {
+            source,
+            gain: gain_node,
+        })
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        let _ = self.ctx.close();
+    }
+}
+
+fn playback_error(err: wasm_bindgen::JsValue) -> Error {
+    Error::PlaybackFailed(util::js_error_to_string(err))
+}
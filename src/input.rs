@@ -1,7 +1,11 @@
 use {
-    crate::app::Message,
+    crate::{app::Message, util},
+    channel::{Channel, Channels},
     derive_more::Display,
-    futures_util::stream,
+    futures_util::{
+        future::{self, Either},
+        stream::{self, AbortHandle},
+    },
     iced::futures::{
         SinkExt,
         Stream,
@@ -11,13 +15,40 @@ use {
             oneshot,
         },
     },
-    midir::MidiInputConnection,
+    midir::{MidiInputConnection, MidiOutputConnection},
     midly::{MidiMessage, live::LiveEvent},
+    pedal::Pedal,
+    recording::Recording,
     serde::{Deserialize, Serialize},
+    std::{
+        cell::RefCell,
+        hash::{DefaultHasher, Hash as _, Hasher as _},
+        rc::Rc,
+        sync::Arc,
+    },
     tap::TapFallible as _,
 };
 
+pub mod channel;
+pub mod output;
+pub mod pedal;
+pub mod qwerty;
+pub mod recording;
+pub mod source;
+mod timestamp;
+
+#[cfg(test)]
+pub mod fixtures;
+
+pub use {
+    source::{Event, EventStream, InputSource},
+    timestamp::Timestamp,
+};
+
 const UNKNOWN_PORT_NAME: &str = "Unknown";
+/// Interval of checking whether the connected device is still available, in
+/// milliseconds.
+const POLL_INTERVAL: i32 = 1000;
 
 #[derive(Debug, thiserror::Error, Clone)]
 pub enum Error {
@@ -32,22 +63,73 @@ pub enum Error {
 
     #[error("Input worker is not available")]
     WorkerNotAvailable,
+
+    #[error("Failed to send midi message: {0}")]
+    SendFailed(String),
+}
+
+impl util::Hint for Error {
+    fn hint(&self) -> &'static str {
+        match self {
+            Self::InitFailed => {
+                "Make sure the browser is allowed to access MIDI devices, or try Google Chrome."
+            }
+            Self::PortNotAvailable => {
+                "Make sure the MIDI device is plugged in and press 'Refresh'."
+            }
+            Self::PortConnectionFailed(_) => {
+                "Try reconnecting the MIDI device, or restart the browser."
+            }
+            Self::WorkerNotAvailable => "Reload the page and try again.",
+            Self::SendFailed(_) => "Try reconnecting the MIDI device.",
+        }
+    }
+}
+
+/// Status of the connection to the selected midi device.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    #[display("Connected")]
+    Connected,
+
+    #[display("Disconnected")]
+    Disconnected,
 }
 
 #[derive(Debug)]
 struct ConnectEvent {
-    port: PortDescriptor,
+    ports: Vec<(PortDescriptor, Channels)>,
     resp: oneshot::Sender<Result<(), Error>>,
 }
 
 #[derive(Default, Debug, Display, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Device {
+    /// Only the on-screen keyboard, which is always available. Only used by
+    /// configs stored by older versions.
     #[default]
     #[display("On-Screen Keyboard")]
     Virtual,
 
+    #[display("Computer Keyboard")]
+    Qwerty,
+
     #[display("{}", _0)]
     Midi(PortDescriptor),
+
+    /// Midi file opened in the main menu, replayed at its original timing.
+    #[display("MIDI File Replay")]
+    Replay,
+}
+
+/// Input device selected in the main menu. Disabled devices are remembered, so
+/// they can be enabled again later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Selection {
+    pub device: Device,
+    pub enabled: bool,
+    /// Channel settings, only used by midi devices.
+    #[serde(default)]
+    pub channels: Channels,
 }
 
 #[derive(Default, Display, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -57,14 +139,33 @@ pub struct PortDescriptor {
     name: String,
 }
 
+impl PortDescriptor {
+    /// Returns the identifier of the port, carried by the events received from
+    /// it.
+    pub fn port_id(&self) -> PortId {
+        let mut hasher = DefaultHasher::new();
+        self.id.hash(&mut hasher);
+        PortId(hasher.finish())
+    }
+}
+
+/// Compact identifier of a midi port, telling apart the events of the merged
+/// ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortId(u64);
+
 #[derive(Debug, Clone)]
 pub struct Connector(UnboundedSender<ConnectEvent>);
 
 impl Connector {
-    pub async fn connect(self, port: PortDescriptor) -> Result<(), Error> {
+    /// Connects to the ports, replacing the existing connections. Events of all
+    /// connected ports are merged.
+    ///
+    /// Ports which are not available are connected once they're plugged in.
+    pub async fn connect(self, ports: Vec<(PortDescriptor, Channels)>) -> Result<(), Error> {
         let (tx, rx) = oneshot::channel();
         let worker_tx = self.0;
-        let connect_evt = ConnectEvent { port, resp: tx };
+        let connect_evt = ConnectEvent { ports, resp: tx };
 
         if worker_tx.unbounded_send(connect_evt).is_err() {
             return Err(Error::WorkerNotAvailable);
@@ -76,15 +177,147 @@ impl Connector {
     }
 }
 
-struct Connection(Option<MidiInputConnection<()>>);
+/// Midi device as an input source. Events are filtered and mapped to hands
+/// according to the channel settings of the device.
+pub struct MidiSource {
+    port: PortDescriptor,
+    channels: Channels,
+    conn: Option<MidiInputConnection<()>>,
+}
+
+impl MidiSource {
+    pub fn new(port: PortDescriptor, channels: Channels) -> Self {
+        Self {
+            port,
+            channels,
+            conn: None,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+}
+
+impl InputSource for MidiSource {
+    fn connect(&mut self) -> Result<EventStream, Error> {
+        self.disconnect();
+
+        let input =
+            midir::MidiInput::new("piano-trainer-read-input").map_err(|_| Error::InitFailed)?;
+
+        let port = input
+            .find_port_by_id(self.port.id.clone())
+            .ok_or(Error::PortNotAvailable)?;
+
+        // The stream ends once the connection is closed, dropping the sender.
+        let (tx, rx) = mpsc::unbounded();
+        let channels = self.channels;
+        let port_id = self.port.port_id();
+
+        let conn = input
+            .connect(
+                &port,
+                "piano-trainer-read-input",
+                move |stamp, message, _| {
+                    if let Some(event) = parse_event(stamp, message, channels, port_id) {
+                        let _ = tx.unbounded_send(event);
+                    }
+                },
+                (),
+            )
+            .map_err(|err| Error::PortConnectionFailed(err.to_string()))?;
+
+        self.conn = Some(conn);
+
+        Ok(rx.boxed_local())
+    }
 
-impl Drop for Connection {
+    fn disconnect(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            conn.close();
+            tracing::info!(port = %self.port, "connection closed");
+        }
+    }
+}
+
+impl Drop for MidiSource {
     fn drop(&mut self) {
-        self.0.take().unwrap().close();
-        tracing::info!("connection closed");
+        self.disconnect();
+    }
+}
+
+/// Midi file replayed at its original timing.
+pub struct ReplaySource {
+    recording: Arc<Recording>,
+    abort: Option<AbortHandle>,
+}
+
+impl ReplaySource {
+    pub fn new(recording: Arc<Recording>) -> Self {
+        Self {
+            recording,
+            abort: None,
+        }
     }
 }
 
+impl InputSource for ReplaySource {
+    fn connect(&mut self) -> Result<EventStream, Error> {
+        let recording = self.recording.clone();
+        let start = Timestamp::now();
+
+        let events = stream::unfold(0, move |idx| {
+            let recording = recording.clone();
+
+            async move {
+                let (offset, recorded) = *recording.events().get(idx)?;
+
+                // Waiting for the event relative to the start of the replay, so the
+                // delays don't accumulate.
+                let delay = offset.saturating_sub(Timestamp::now().duration_since(start));
+
+                if delay.as_millis() > 0 {
+                    util::sleep(delay.as_millis() as i32).await;
+                }
+
+                let event = Event {
+                    message: recorded.message,
+                    hand: recorded.hand,
+                    channel: Some(recorded.channel),
+                    port: None,
+                    stamp: Timestamp::now(),
+                };
+
+                Some((event, idx + 1))
+            }
+        });
+
+        Ok(source::abortable(events, &mut self.abort))
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(abort) = self.abort.take() {
+            abort.abort();
+        }
+    }
+}
+
+impl Drop for ReplaySource {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// Port the worker stays connected to.
+struct Target {
+    /// Port as requested by the app, used to report the status.
+    requested: PortDescriptor,
+    /// Source of the port as currently available, which may have a different
+    /// ID after reconnecting the device.
+    source: MidiSource,
+}
+
 pub fn connection_worker() -> impl Stream<Item = Message> {
     let (mut out_tx, out_rx) = mpsc::unbounded();
     let (worker_tx, mut worker_rx) = mpsc::unbounded();
@@ -96,30 +329,41 @@ pub fn connection_worker() -> impl Stream<Item = Message> {
                 .send(Message::InputWorkerReady(Connector(worker_tx)))
                 .await;
 
-            let mut _conn = None;
+            // The ports to stay connected to. Reconnects automatically if a device
+            // is unplugged and plugged in again.
+            let mut targets = Vec::new();
+
+            loop {
+                let poll = Box::pin(util::sleep(POLL_INTERVAL));
 
-            while let Some(ConnectEvent { port, resp }) = worker_rx.next().await {
-                let result = connect(port, out_tx.clone()).map(|conn| {
-                    _conn = Some(Connection(Some(conn)));
-                });
+                match future::select(worker_rx.next(), poll).await {
+                    Either::Left((Some(ConnectEvent { ports, resp }), _)) => {
+                        targets.clear();
 
-                let _ = resp.send(result);
+                        let result = connect_all(ports, &out_tx).map(|new_targets| {
+                            targets = new_targets;
+                        });
+
+                        let _ = resp.send(result);
+                    }
+
+                    Either::Left((None, _)) => break,
+
+                    Either::Right(_) => poll_targets(&mut targets, &out_tx),
+                }
             }
         })
         .filter_map(|_| async { None }),
     )
 }
 
-pub fn port_list() -> Vec<PortDescriptor> {
-    let result = midir::MidiInput::new("piano trainer device list").map_err(|err| {
+pub fn port_list() -> Result<Vec<PortDescriptor>, Error> {
+    let midi_in = midir::MidiInput::new("piano trainer device list").map_err(|err| {
         tracing::warn!(?err, "failed to refresh input ports");
-    });
+        Error::InitFailed
+    })?;
 
-    let Ok(midi_in) = result else {
-        return Vec::new();
-    };
-
-    midi_in
+    let ports = midi_in
         .ports()
         .into_iter()
         .map(|port| {
@@ -132,49 +376,224 @@ pub fn port_list() -> Vec<PortDescriptor> {
                 name,
             }
         })
-        .collect()
+        .collect();
+
+    Ok(ports)
 }
 
-fn connect(
-    port: PortDescriptor,
-    tx: UnboundedSender<Message>,
-) -> Result<MidiInputConnection<()>, Error> {
-    let input = midir::MidiInput::new("piano-trainer-read-input").map_err(|_| Error::InitFailed)?;
+/// Connects to each of the ports. Ports which fail to connect are retried when
+/// polling, so one faulty device doesn't prevent using the others.
+fn connect_all(
+    ports: Vec<(PortDescriptor, Channels)>,
+    out_tx: &UnboundedSender<Message>,
+) -> Result<Vec<Target>, Error> {
+    let mut targets = Vec::with_capacity(ports.len());
+
+    for (port, channels) in ports {
+        let mut source = MidiSource::new(port.clone(), channels);
+
+        match forward(&mut source, out_tx) {
+            Ok(_) => {}
+            Err(Error::InitFailed) => return Err(Error::InitFailed),
+            Err(err) => tracing::warn!(?err, %port, "failed to connect input port"),
+        }
+
+        send_status(out_tx, &port, source.is_connected());
+
+        targets.push(Target {
+            requested: port,
+            source,
+        });
+    }
+
+    Ok(targets)
+}
+
+fn poll_targets(targets: &mut [Target], out_tx: &UnboundedSender<Message>) {
+    if targets.is_empty() {
+        return;
+    }
+
+    let ports = port_list().unwrap_or_default();
+
+    for idx in 0..targets.len() {
+        // Ports connected by other targets, in case several identical devices
+        // are used.
+        let taken = targets
+            .iter()
+            .filter(|target| target.source.is_connected())
+            .map(|target| target.source.port.id.clone())
+            .collect::<Vec<_>>();
+
+        let target = &mut targets[idx];
+
+        if target.source.is_connected() {
+            if !ports.iter().any(|port| port.id == target.source.port.id) {
+                tracing::info!(port = %target.source.port, "input device disconnected");
+                target.source.disconnect();
+                send_status(out_tx, &target.requested, false);
+            }
 
-    let port = input
+            continue;
+        }
+
+        let Some(port) = find_port(&target.source.port, &ports, &taken) else {
+            continue;
+        };
+
+        let mut source = MidiSource::new(port.clone(), target.source.channels);
+
+        if forward(&mut source, out_tx)
+            .tap_err(|err| tracing::warn!(?err, "failed to reconnect"))
+            .is_ok()
+        {
+            tracing::info!(%port, "input device reconnected");
+            target.source = source;
+            send_status(out_tx, &target.requested, true);
+        }
+    }
+}
+
+pub fn output_port_list() -> Result<Vec<PortDescriptor>, Error> {
+    let midi_out = midir::MidiOutput::new("piano trainer output list").map_err(|err| {
+        tracing::warn!(?err, "failed to refresh output ports");
+        Error::InitFailed
+    })?;
+
+    let ports = midi_out
+        .ports()
+        .into_iter()
+        .map(|port| {
+            let name = midi_out
+                .port_name(&port)
+                .unwrap_or_else(|_| UNKNOWN_PORT_NAME.to_owned());
+
+            PortDescriptor {
+                id: port.id(),
+                name,
+            }
+        })
+        .collect();
+
+    Ok(ports)
+}
+
+/// Output connection to a midi device. Clones share the connection, so that the
+/// instrument can be silenced once the page is closed.
+#[derive(Clone)]
+pub struct MidiSink(Rc<RefCell<MidiOutputConnection>>);
+
+impl output::Sink for MidiSink {
+    fn send(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.0
+            .borrow_mut()
+            .send(message)
+            .map_err(|err| Error::SendFailed(err.to_string()))
+    }
+}
+
+pub fn connect_output(port: &PortDescriptor) -> Result<MidiSink, Error> {
+    let output =
+        midir::MidiOutput::new("piano-trainer-write-output").map_err(|_| Error::InitFailed)?;
+
+    let port = output
         .find_port_by_id(port.id.clone())
         .ok_or(Error::PortNotAvailable)?;
 
-    input
-        .connect(
-            &port,
-            "piano-trainer-read-input",
-            move |stamp, message, _| process_event(stamp, message, &tx),
-            (),
-        )
+    output
+        .connect(&port, "piano-trainer-write-output")
+        .map(|conn| MidiSink(Rc::new(RefCell::new(conn))))
         .map_err(|err| Error::PortConnectionFailed(err.to_string()))
 }
 
-fn process_event(stamp: u64, message: &[u8], out_tx: &UnboundedSender<Message>) {
-    tracing::trace!("{}: {:?} (len = {})", stamp, message, message.len());
+/// Finds the port among the available ports, falling back to matching by name
+/// in case the port ID changed after reconnecting the device.
+fn find_port(
+    port: &PortDescriptor,
+    ports: &[PortDescriptor],
+    taken: &[String],
+) -> Option<PortDescriptor> {
+    let mut ports = ports
+        .iter()
+        .filter(|candidate| !taken.contains(&candidate.id));
+
+    ports
+        .clone()
+        .find(|candidate| candidate.id == port.id)
+        .or_else(|| ports.find(|candidate| candidate.name == port.name))
+        .cloned()
+}
 
-    let Ok(event) = LiveEvent::parse(message).tap_err(|err| {
-        tracing::warn!(?err, "failed to parse midi message");
-    }) else {
-        return;
+fn send_status(out_tx: &UnboundedSender<Message>, port: &PortDescriptor, connected: bool) {
+    let status = if connected {
+        Status::Connected
+    } else {
+        Status::Disconnected
     };
 
-    let event = match event {
-        LiveEvent::Midi { message, .. } => match message {
-            // Some keyboards send `NoteOn` event with vel 0 instead of `NoteOff`.
-            MidiMessage::NoteOn { key, vel } if vel == 0 => Some(MidiMessage::NoteOff { key, vel }),
-            MidiMessage::NoteOff { .. } | MidiMessage::NoteOn { .. } => Some(message),
-            _ => None,
-        },
-        _ => None,
+    let _ = out_tx.unbounded_send(Message::InputStatus {
+        port: port.clone(),
+        status,
+    });
+}
+
+/// Connects the source, forwarding its events to the app until it's
+/// disconnected.
+fn forward(source: &mut impl InputSource, out_tx: &UnboundedSender<Message>) -> Result<(), Error> {
+    let mut events = source.connect()?;
+    let out_tx = out_tx.clone();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        while let Some(event) = events.next().await {
+            if out_tx.unbounded_send(Message::Input(event)).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn parse_event(stamp: u64, message: &[u8], channels: Channels, port_id: PortId) -> Option<Event> {
+    tracing::trace!("{}: {:?} (len = {})", stamp, message, message.len());
+
+    let event = LiveEvent::parse(message)
+        .tap_err(|err| {
+            tracing::warn!(?err, "failed to parse midi message");
+        })
+        .ok()?;
+
+    let LiveEvent::Midi { channel, message } = event else {
+        return None;
     };
 
-    if let Some(event) = event {
-        let _ = out_tx.unbounded_send(Message::InputEvent(event));
+    let channel = Channel::from_midi(channel);
+
+    if !channels.accepts(channel) {
+        return None;
+    }
+
+    Some(Event {
+        message: filter_message(message)?,
+        hand: channels.hand(channel),
+        channel: Some(channel),
+        port: Some(port_id),
+        stamp: Timestamp::from_micros(stamp),
+    })
+}
+
+/// Returns the message if it's handled by the game, i.e. it's a note or a pedal
+/// event.
+fn filter_message(message: MidiMessage) -> Option<MidiMessage> {
+    match message {
+        // Some keyboards send `NoteOn` event with vel 0 instead of `NoteOff`.
+        MidiMessage::NoteOn { key, vel } if vel == 0 => Some(MidiMessage::NoteOff { key, vel }),
+        MidiMessage::NoteOff { .. } | MidiMessage::NoteOn { .. } => Some(message),
+        MidiMessage::Controller { controller, .. }
+            if Pedal::from_controller(controller).is_some() =>
+        {
+            Some(message)
+        }
+        _ => None,
     }
 }
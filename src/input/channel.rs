@@ -0,0 +1,153 @@
+use {
+    derive_more::Display,
+    midly::num::u4,
+    serde::{Deserialize, Serialize},
+};
+
+/// Midi channel, numbered from 1 to 16 as shown by most devices.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
+#[display("Ch. {_0}")]
+pub struct Channel(u8);
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid MIDI channel: {0}")]
+pub struct InvalidChannel(u8);
+
+impl Channel {
+    pub const ALL: [Self; 16] = [
+        Self(1),
+        Self(2),
+        Self(3),
+        Self(4),
+        Self(5),
+        Self(6),
+        Self(7),
+        Self(8),
+        Self(9),
+        Self(10),
+        Self(11),
+        Self(12),
+        Self(13),
+        Self(14),
+        Self(15),
+        Self(16),
+    ];
+
+    pub fn from_midi(channel: u4) -> Self {
+        Self(channel.as_int() + 1)
+    }
+
+    pub fn to_midi(&self) -> u4 {
+        u4::new(self.0 - 1)
+    }
+}
+
+impl TryFrom<u8> for Channel {
+    type Error = InvalidChannel;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if (1..=16).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(InvalidChannel(value))
+        }
+    }
+}
+
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    #[display("Left hand")]
+    Left,
+
+    #[display("Right hand")]
+    Right,
+}
+
+/// Channel settings of a midi device.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Channels {
+    /// Only events on this channel are accepted, e.g. to ignore the layered
+    /// sound of a keyboard. Channels mapped to a hand are accepted as well.
+    pub filter: Option<Channel>,
+    /// Channel of the right hand part of a split keyboard.
+    pub right_hand: Option<Channel>,
+    /// Channel of the left hand part of a split keyboard.
+    pub left_hand: Option<Channel>,
+}
+
+impl Channels {
+    pub fn accepts(&self, channel: Channel) -> bool {
+        self.filter.is_none_or(|filter| filter == channel) || self.hand(channel).is_some()
+    }
+
+    /// Returns the hand playing on the channel, if it's mapped to one.
+    pub fn hand(&self, channel: Channel) -> Option<Hand> {
+        if self.right_hand == Some(channel) {
+            Some(Hand::Right)
+        } else if self.left_hand == Some(channel) {
+            Some(Hand::Left)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, wasm_bindgen_test::*};
+
+    #[wasm_bindgen_test]
+    fn routing() {
+        let ch = |num: u8| Channel::from_midi(u4::new(num - 1));
+
+        let channels = Channels::default();
+        assert!(
+            Channel::ALL
+                .into_iter()
+                .all(|channel| channels.accepts(channel))
+        );
+        assert_eq!(channels.hand(ch(1)), None);
+
+        let channels = Channels {
+            filter: Some(ch(2)),
+            ..Default::default()
+        };
+        assert!(!channels.accepts(ch(1)));
+        assert!(channels.accepts(ch(2)));
+
+        let channels = Channels {
+            filter: None,
+            right_hand: Some(ch(1)),
+            left_hand: Some(ch(2)),
+        };
+        assert!(channels.accepts(ch(3)));
+        assert_eq!(channels.hand(ch(1)), Some(Hand::Right));
+        assert_eq!(channels.hand(ch(2)), Some(Hand::Left));
+        assert_eq!(channels.hand(ch(3)), None);
+
+        // Hand channels pass the filter.
+        let channels = Channels {
+            filter: Some(ch(3)),
+            right_hand: Some(ch(1)),
+            left_hand: Some(ch(2)),
+        };
+        assert!(channels.accepts(ch(1)));
+        assert!(channels.accepts(ch(2)));
+        assert!(channels.accepts(ch(3)));
+        assert!(!channels.accepts(ch(4)));
+        assert_eq!(ch(16), Channel::ALL[15]);
+    }
+
+    #[wasm_bindgen_test]
+    fn stored_channels() {
+        assert_eq!(serde_json::to_string(&Channel::ALL[0]).unwrap(), "1");
+        assert_eq!(
+            serde_json::from_str::<Channel>("16").unwrap(),
+            Channel::ALL[15]
+        );
+        assert!(serde_json::from_str::<Channel>("0").is_err());
+        assert!(serde_json::from_str::<Channel>("17").is_err());
+    }
+}
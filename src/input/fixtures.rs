@@ -0,0 +1,28 @@
+//! Midi messages and ports shared by the input tests.
+
+use {super::PortDescriptor, midly::MidiMessage};
+
+pub fn port(id: &str) -> PortDescriptor {
+    PortDescriptor {
+        id: id.to_owned(),
+        name: id.to_owned(),
+    }
+}
+
+pub fn note_on(key: u8) -> MidiMessage {
+    note_on_with_velocity(key, 64)
+}
+
+pub fn note_on_with_velocity(key: u8, vel: u8) -> MidiMessage {
+    MidiMessage::NoteOn {
+        key: key.into(),
+        vel: vel.into(),
+    }
+}
+
+pub fn note_off(key: u8) -> MidiMessage {
+    MidiMessage::NoteOff {
+        key: key.into(),
+        vel: 0.into(),
+    }
+}
@@ -0,0 +1,329 @@
+use {
+    super::{Error, PortDescriptor, channel::Channel},
+    derive_more::Display,
+    midly::{MidiMessage, live::LiveEvent, num::u7},
+    serde::{Deserialize, Serialize},
+    tap::TapFallible as _,
+};
+
+/// "All Notes Off" channel mode message.
+const ALL_NOTES_OFF: u8 = 123;
+/// Velocity of the notes lighting up the keys. Keyboards with key lights
+/// usually don't play notes on the light channel, but the velocity is kept low
+/// in case they do.
+const LIGHT_VELOCITY: u8 = 1;
+const FEEDBACK_VELOCITY: u8 = 60;
+/// Major third, played when the challenge is completed.
+const CORRECT_NOTES: [u8; 2] = [84, 88];
+/// Minor second, played when a wrong key is pressed.
+const INCORRECT_NOTES: [u8; 2] = [36, 37];
+/// Duration of the feedback sounds, in milliseconds.
+pub const FEEDBACK_DURATION: i32 = 150;
+
+/// Destination of the midi messages sent to the instrument.
+pub trait Sink {
+    fn send(&mut self, message: &[u8]) -> Result<(), Error>;
+}
+
+#[derive(Default, Debug, Display, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Device {
+    #[default]
+    #[display("No output")]
+    None,
+
+    #[display("{}", _0)]
+    Midi(PortDescriptor),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub device: Device,
+    /// Lights up the expected keys on keyboards with key lights.
+    pub lights: bool,
+    /// Channel the key lights are controlled with. Notes on other channels are
+    /// played by most keyboards, so it defaults to the last channel, which is
+    /// rarely used for playing.
+    pub light_channel: Channel,
+    /// Plays the feedback sounds on the instrument.
+    pub sounds: bool,
+    /// Channel the feedback sounds are played on.
+    pub feedback_channel: Channel,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            device: Device::None,
+            lights: false,
+            light_channel: Channel::ALL[15],
+            sounds: false,
+            feedback_channel: Channel::ALL[0],
+        }
+    }
+}
+
+/// Sends the expected keys and the feedback sounds to the instrument.
+pub struct Output<S: Sink> {
+    sink: S,
+    config: Config,
+    lit: Vec<u7>,
+    sounding: Vec<u7>,
+}
+
+impl<S: Sink> Output<S> {
+    pub fn new(sink: S, config: Config) -> Self {
+        Self {
+            sink,
+            config,
+            lit: Vec::new(),
+            sounding: Vec::new(),
+        }
+    }
+
+    /// Lights up the keys, turning off the previously lit ones.
+    pub fn light_keys(&mut self, keys: impl IntoIterator<Item = u7>) {
+        if !self.config.lights {
+            return;
+        }
+
+        for key in std::mem::take(&mut self.lit) {
+            self.send(self.config.light_channel, MidiMessage::NoteOff {
+                key,
+                vel: 0.into(),
+            });
+        }
+
+        for key in keys {
+            self.send(self.config.light_channel, MidiMessage::NoteOn {
+                key,
+                vel: LIGHT_VELOCITY.into(),
+            });
+            self.lit.push(key);
+        }
+    }
+
+    /// Turns off the light of the key, e.g. once it's played correctly.
+    pub fn unlight_key(&mut self, key: u7) {
+        if let Some(idx) = self.lit.iter().position(|lit| *lit == key) {
+            self.lit.remove(idx);
+            self.send(self.config.light_channel, MidiMessage::NoteOff {
+                key,
+                vel: 0.into(),
+            });
+        }
+    }
+
+    /// Starts playing the feedback sound. Returns whether the sound is playing,
+    /// and needs to be released with [`Self::release_feedback`].
+    pub fn play_feedback(&mut self, correct: bool) -> bool {
+        if !self.config.sounds {
+            return false;
+        }
+
+        self.release_feedback();
+
+        let notes = if correct {
+            CORRECT_NOTES
+        } else {
+            INCORRECT_NOTES
+        };
+
+        for key in notes.map(u7::new) {
+            self.send(self.config.feedback_channel, MidiMessage::NoteOn {
+                key,
+                vel: FEEDBACK_VELOCITY.into(),
+            });
+            self.sounding.push(key);
+        }
+
+        true
+    }
+
+    pub fn release_feedback(&mut self) {
+        let channel = self.config.feedback_channel;
+
+        for key in std::mem::take(&mut self.sounding) {
+            self.send(channel, MidiMessage::NoteOff { key, vel: 0.into() });
+
+            // The note off turns off the light of the key as well, if the
+            // feedback is played on the light channel.
+            if channel == self.config.light_channel && self.lit.contains(&key) {
+                self.send(channel, MidiMessage::NoteOn {
+                    key,
+                    vel: LIGHT_VELOCITY.into(),
+                });
+            }
+        }
+    }
+
+    /// Silences all notes and lights on every channel.
+    pub fn all_notes_off(&mut self) {
+        self.lit.clear();
+        self.sounding.clear();
+        all_notes_off(&mut self.sink);
+    }
+
+    fn send(&mut self, channel: Channel, message: MidiMessage) {
+        send(&mut self.sink, channel, message);
+    }
+}
+
+/// Silences all notes and lights of the instrument on every channel, e.g. once
+/// the page is closed and the [`Output`] is never dropped.
+pub fn all_notes_off(sink: &mut impl Sink) {
+    for channel in Channel::ALL {
+        send(sink, channel, MidiMessage::Controller {
+            controller: ALL_NOTES_OFF.into(),
+            value: 0.into(),
+        });
+    }
+}
+
+fn send(sink: &mut impl Sink, channel: Channel, message: MidiMessage) {
+    let mut buf = Vec::with_capacity(3);
+    let event = LiveEvent::Midi {
+        channel: channel.to_midi(),
+        message,
+    };
+
+    if event.write_std(&mut buf).is_ok() {
+        let _ = sink
+            .send(&buf)
+            .tap_err(|err| tracing::warn!(?err, "failed to send midi message"));
+    }
+}
+
+impl<S: Sink> Drop for Output<S> {
+    fn drop(&mut self) {
+        self.all_notes_off();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        std::{cell::RefCell, rc::Rc},
+        wasm_bindgen_test::*,
+    };
+
+    /// Sink recording the sent messages.
+    #[derive(Default, Clone)]
+    struct MockSink(Rc<RefCell<Vec<Vec<u8>>>>);
+
+    impl MockSink {
+        fn take(&self) -> Vec<Vec<u8>> {
+            std::mem::take(&mut self.0.borrow_mut())
+        }
+    }
+
+    impl Sink for MockSink {
+        fn send(&mut self, message: &[u8]) -> Result<(), Error> {
+            self.0.borrow_mut().push(message.to_vec());
+            Ok(())
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            device: Device::None,
+            lights: true,
+            light_channel: Channel::ALL[15],
+            sounds: true,
+            feedback_channel: Channel::ALL[0],
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn lights() {
+        let sink = MockSink::default();
+        let mut output = Output::new(sink.clone(), config());
+
+        output.light_keys([60.into(), 64.into()]);
+        assert_eq!(sink.take(), [vec![0x9f, 60, 1], vec![0x9f, 64, 1]]);
+
+        output.unlight_key(60.into());
+        output.unlight_key(62.into());
+        assert_eq!(sink.take(), [vec![0x8f, 60, 0]]);
+
+        output.light_keys([67.into()]);
+        assert_eq!(sink.take(), [vec![0x8f, 64, 0], vec![0x9f, 67, 1]]);
+
+        let mut output = Output::new(sink.clone(), Config {
+            lights: false,
+            ..config()
+        });
+
+        output.light_keys([60.into()]);
+        assert_eq!(sink.take(), Vec::<Vec<u8>>::new());
+    }
+
+    #[wasm_bindgen_test]
+    fn feedback() {
+        let sink = MockSink::default();
+        let mut output = Output::new(sink.clone(), config());
+
+        assert!(output.play_feedback(false));
+        assert_eq!(sink.take(), [vec![0x90, 36, 60], vec![0x90, 37, 60]]);
+
+        // A new sound stops the previous one.
+        assert!(output.play_feedback(true));
+        assert_eq!(sink.take(), [
+            vec![0x80, 36, 0],
+            vec![0x80, 37, 0],
+            vec![0x90, 84, 60],
+            vec![0x90, 88, 60],
+        ]);
+
+        output.release_feedback();
+        output.release_feedback();
+        assert_eq!(sink.take(), [vec![0x80, 84, 0], vec![0x80, 88, 0]]);
+
+        let mut output = Output::new(sink.clone(), Config {
+            feedback_channel: Channel::ALL[2],
+            ..config()
+        });
+
+        output.play_feedback(true);
+        output.release_feedback();
+        assert_eq!(sink.take(), [
+            vec![0x92, 84, 60],
+            vec![0x92, 88, 60],
+            vec![0x82, 84, 0],
+            vec![0x82, 88, 0],
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    fn feedback_on_light_channel() {
+        let sink = MockSink::default();
+        let mut output = Output::new(sink.clone(), Config {
+            feedback_channel: Channel::ALL[15],
+            ..config()
+        });
+
+        output.light_keys([84.into()]);
+        output.play_feedback(true);
+        sink.take();
+
+        // The lit key is lit again once the feedback is released.
+        output.release_feedback();
+        assert_eq!(sink.take(), [vec![0x8f, 84, 0], vec![0x9f, 84, 1], vec![
+            0x8f, 88, 0
+        ],]);
+    }
+
+    #[wasm_bindgen_test]
+    fn all_notes_off_on_drop() {
+        let sink = MockSink::default();
+        let output = Output::new(sink.clone(), config());
+
+        drop(output);
+
+        let sent = sink.take();
+        assert_eq!(sent.len(), 16);
+        assert_eq!(sent[0], [0xb0, 123, 0]);
+        assert_eq!(sent[15], [0xbf, 123, 0]);
+    }
+}
@@ -0,0 +1,311 @@
+use {
+    derive_more::Display,
+    midly::{MidiMessage, num::u7},
+    serde::{Deserialize, Serialize},
+    std::collections::HashSet,
+};
+
+/// Pedals are pressed when the controller value is at least half of the range.
+const PRESSED_THRESHOLD: u8 = 64;
+
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Pedal {
+    #[display("Sustain")]
+    Sustain,
+
+    #[display("Sostenuto")]
+    Sostenuto,
+
+    #[display("Soft")]
+    Soft,
+}
+
+impl Pedal {
+    pub const ALL: [Self; 3] = [Self::Sustain, Self::Sostenuto, Self::Soft];
+
+    pub fn from_controller(controller: u7) -> Option<Self> {
+        match controller.as_int() {
+            64 => Some(Self::Sustain),
+            66 => Some(Self::Sostenuto),
+            67 => Some(Self::Soft),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Note(MidiMessage),
+    Pedal { pedal: Pedal, pressed: bool },
+}
+
+/// Tracks the pedals and the keys held on the keyboard, so that notes sustained
+/// by the pedals are released only once they stop sounding.
+#[derive(Debug, Default, Clone)]
+pub struct Tracker {
+    /// Pedals bound to game actions, which don't affect the notes.
+    bound: HashSet<Pedal>,
+    pressed: HashSet<Pedal>,
+    /// Keys held down on the keyboard.
+    held: HashSet<u7>,
+    /// Keys released on the keyboard, which are still sounding.
+    sustained: HashSet<u7>,
+    /// Keys held when the sostenuto pedal was pressed.
+    sostenuto: HashSet<u7>,
+}
+
+impl Tracker {
+    pub fn new(bound: impl IntoIterator<Item = Pedal>) -> Self {
+        Self {
+            bound: bound.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_pressed(&self, pedal: Pedal) -> bool {
+        self.pressed.contains(&pedal)
+    }
+
+    /// Processes the midi message, returning the resulting note and pedal
+    /// events.
+    pub fn process(&mut self, message: MidiMessage) -> Vec<Event> {
+        match message {
+            MidiMessage::NoteOn { key, .. } => {
+                // Ignore repeated presses of a held key.
+                if !self.held.insert(key) {
+                    return Vec::new();
+                }
+
+                self.sustained.remove(&key);
+                vec![Event::Note(message)]
+            }
+
+            MidiMessage::NoteOff { key, .. } => {
+                if self.held.remove(&key) && self.is_sounding(key) {
+                    self.sustained.insert(key);
+                    return Vec::new();
+                }
+
+                vec![Event::Note(message)]
+            }
+
+            MidiMessage::Controller { controller, value } => {
+                let Some(pedal) = Pedal::from_controller(controller) else {
+                    return Vec::new();
+                };
+
+                let pressed = value.as_int() >= PRESSED_THRESHOLD;
+
+                // Pedals with continuous values send many events while being pressed.
+                if pressed == self.is_pressed(pedal) {
+                    return Vec::new();
+                }
+
+                if pressed {
+                    self.pressed.insert(pedal);
+                } else {
+                    self.pressed.remove(&pedal);
+                }
+
+                let mut events = vec![Event::Pedal { pedal, pressed }];
+
+                if self.bound.contains(&pedal) {
+                    return events;
+                }
+
+                match (pedal, pressed) {
+                    (Pedal::Sostenuto, true) => self.sostenuto = self.held.clone(),
+                    (Pedal::Sostenuto, false) => self.sostenuto.clear(),
+                    _ => {}
+                }
+
+                if !pressed {
+                    events.extend(self.release_silenced());
+                }
+
+                events
+            }
+
+            _ => Vec::new(),
+        }
+    }
+
+    /// Releases all keys and pedals, e.g. once a device is disconnected and its
+    /// note and pedal offs are lost. Returns the resulting note and pedal
+    /// events.
+    pub fn reset(&mut self) -> Vec<Event> {
+        let mut keys = self
+            .held
+            .drain()
+            .chain(self.sustained.drain())
+            .collect::<Vec<_>>();
+        let pedals = Pedal::ALL
+            .into_iter()
+            .filter(|pedal| self.pressed.remove(pedal))
+            .collect::<Vec<_>>();
+
+        keys.sort();
+        self.sostenuto.clear();
+
+        pedals
+            .into_iter()
+            .map(|pedal| Event::Pedal {
+                pedal,
+                pressed: false,
+            })
+            .chain(
+                keys.into_iter()
+                    .map(|key| Event::Note(MidiMessage::NoteOff { key, vel: 0.into() })),
+            )
+            .collect()
+    }
+
+    /// Returns whether the key keeps sounding after being released.
+    fn is_sounding(&self, key: u7) -> bool {
+        let is_active = |pedal| self.is_pressed(pedal) && !self.bound.contains(&pedal);
+
+        is_active(Pedal::Sustain) || (is_active(Pedal::Sostenuto) && self.sostenuto.contains(&key))
+    }
+
+    fn release_silenced(&mut self) -> Vec<Event> {
+        let mut silenced = self
+            .sustained
+            .iter()
+            .copied()
+            .filter(|key| !self.is_sounding(*key))
+            .collect::<Vec<_>>();
+
+        silenced.sort();
+
+        silenced
+            .into_iter()
+            .map(|key| {
+                self.sustained.remove(&key);
+
+                Event::Note(MidiMessage::NoteOff { key, vel: 0.into() })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::input::fixtures::{note_off, note_on},
+        wasm_bindgen_test::*,
+    };
+
+    fn pedal(pedal: Pedal, value: u8) -> MidiMessage {
+        let controller = match pedal {
+            Pedal::Sustain => 64,
+            Pedal::Sostenuto => 66,
+            Pedal::Soft => 67,
+        };
+
+        MidiMessage::Controller {
+            controller: controller.into(),
+            value: value.into(),
+        }
+    }
+
+    fn notes(events: Vec<Event>) -> Vec<MidiMessage> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Note(message) => Some(message),
+                Event::Pedal { .. } => None,
+            })
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn sustain() {
+        let mut tracker = Tracker::default();
+
+        assert_eq!(tracker.process(note_on(60)), [Event::Note(note_on(60))]);
+        assert_eq!(tracker.process(note_on(60)), []);
+
+        assert_eq!(tracker.process(pedal(Pedal::Sustain, 127)), [
+            Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: true
+            }
+        ]);
+        assert_eq!(tracker.process(pedal(Pedal::Sustain, 100)), []);
+        assert!(tracker.is_pressed(Pedal::Sustain));
+
+        // Released keys keep sounding while the pedal is pressed.
+        assert_eq!(tracker.process(note_off(60)), []);
+        assert_eq!(tracker.process(note_on(62)), [Event::Note(note_on(62))]);
+        assert_eq!(tracker.process(note_off(62)), []);
+
+        // Pressing a sustained key again is a new press.
+        assert_eq!(tracker.process(note_on(60)), [Event::Note(note_on(60))]);
+
+        assert_eq!(notes(tracker.process(pedal(Pedal::Sustain, 0))), [
+            note_off(62)
+        ]);
+        assert_eq!(tracker.process(note_off(60)), [Event::Note(note_off(60))]);
+    }
+
+    #[wasm_bindgen_test]
+    fn sostenuto() {
+        let mut tracker = Tracker::default();
+
+        tracker.process(note_on(48));
+        tracker.process(pedal(Pedal::Sostenuto, 127));
+        tracker.process(note_on(60));
+
+        // Only the keys held when the pedal was pressed are sustained.
+        assert_eq!(tracker.process(note_off(48)), []);
+        assert_eq!(notes(tracker.process(note_off(60))), [note_off(60)]);
+        assert_eq!(notes(tracker.process(pedal(Pedal::Sostenuto, 0))), [
+            note_off(48)
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    fn bound_pedals() {
+        let mut tracker = Tracker::new([Pedal::Sustain]);
+
+        tracker.process(note_on(60));
+
+        assert_eq!(tracker.process(pedal(Pedal::Sustain, 127)), [
+            Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: true
+            }
+        ]);
+        assert_eq!(notes(tracker.process(note_off(60))), [note_off(60)]);
+
+        // The soft pedal doesn't sustain notes.
+        tracker.process(pedal(Pedal::Soft, 127));
+        tracker.process(note_on(62));
+        assert_eq!(notes(tracker.process(note_off(62))), [note_off(62)]);
+    }
+
+    #[wasm_bindgen_test]
+    fn reset() {
+        let mut tracker = Tracker::default();
+
+        tracker.process(note_on(60));
+        tracker.process(pedal(Pedal::Sustain, 127));
+        tracker.process(note_on(62));
+        tracker.process(note_off(62));
+
+        assert_eq!(tracker.reset(), [
+            Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: false
+            },
+            Event::Note(note_off(60)),
+            Event::Note(note_off(62)),
+        ]);
+        assert!(!tracker.is_pressed(Pedal::Sustain));
+        assert_eq!(tracker.reset(), []);
+
+        // Keys held before the reset can be pressed again.
+        assert_eq!(tracker.process(note_on(60)), [Event::Note(note_on(60))]);
+    }
+}
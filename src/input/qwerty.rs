@@ -0,0 +1,263 @@
+use {
+    crate::{
+        app::Message,
+        input::Timestamp,
+        keyboard::{Key, KeyPos},
+    },
+    iced::{
+        event,
+        keyboard::{self, key::Physical},
+        window,
+    },
+    midly::MidiMessage,
+    serde::{Deserialize, Serialize},
+    std::collections::HashMap,
+};
+
+pub const MAX_OCTAVE: u8 = 7;
+/// Velocity of the played notes, since computer keys aren't velocity-sensitive.
+const VELOCITY: u8 = 100;
+
+/// Mapping of the computer keyboard to piano keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mapping {
+    /// Characters mapped to consecutive semitones, starting with the C of the
+    /// base octave.
+    pub keys: String,
+    /// Shifts the mapped keys one octave down.
+    pub octave_down: char,
+    /// Shifts the mapped keys one octave up.
+    pub octave_up: char,
+    /// Octave of the first mapped key.
+    pub octave: u8,
+}
+
+impl Default for Mapping {
+    /// The "piano layout": the home row plays natural keys and the row above it
+    /// plays sharp keys.
+    fn default() -> Self {
+        Self {
+            keys: "awsedftgyhujkolp;'".to_owned(),
+            octave_down: 'z',
+            octave_up: 'x',
+            octave: 4,
+        }
+    }
+}
+
+/// Converts computer keyboard events into midi messages.
+#[derive(Debug, Clone)]
+pub struct Mapper {
+    mapping: Mapping,
+    octave: u8,
+    /// Held keys by their physical key, since the character of a key changes
+    /// with modifiers, e.g. `;` is released as `:` if Shift is pressed in the
+    /// meantime.
+    held: HashMap<Physical, Key>,
+}
+
+impl Mapper {
+    pub fn new(mapping: Mapping) -> Self {
+        let octave = mapping.octave.min(MAX_OCTAVE);
+
+        Self {
+            mapping,
+            octave,
+            held: HashMap::new(),
+        }
+    }
+
+    pub fn octave(&self) -> u8 {
+        self.octave
+    }
+
+    pub fn key_pressed(&mut self, ch: char, code: Physical) -> Option<MidiMessage> {
+        let ch = ch.to_ascii_lowercase();
+
+        if ch == self.mapping.octave_down {
+            self.octave = self.octave.saturating_sub(1);
+            return None;
+        }
+
+        if ch == self.mapping.octave_up {
+            self.octave = (self.octave + 1).min(MAX_OCTAVE);
+            return None;
+        }
+
+        // Ignore auto-repeated presses while the key is held.
+        if self.held.contains_key(&code) {
+            return None;
+        }
+
+        let key = self.key(ch)?;
+        self.held.insert(code, key);
+
+        Some(MidiMessage::NoteOn {
+            key: key.to_midi(),
+            vel: VELOCITY.into(),
+        })
+    }
+
+    pub fn key_released(&mut self, code: Physical) -> Option<MidiMessage> {
+        // The key is released using the octave it was pressed with, in case the
+        // octave was shifted in the meantime.
+        let key = self.held.remove(&code)?;
+
+        Some(MidiMessage::NoteOff {
+            key: key.to_midi(),
+            vel: 0.into(),
+        })
+    }
+
+    fn key(&self, ch: char) -> Option<Key> {
+        let semitones = self.mapping.keys.chars().position(|key| key == ch)?;
+
+        KeyPos::C
+            .oct(self.octave)
+            .transpose(i16::try_from(semitones).ok()?)
+            .ok()
+    }
+}
+
+/// Subscription listener for computer keyboard events, ignoring events already
+/// captured by other widgets.
+pub fn listen(event: iced::Event, status: event::Status, _: window::Id) -> Option<Message> {
+    if status == event::Status::Captured {
+        return None;
+    }
+
+    match event {
+        iced::Event::Keyboard(keyboard::Event::KeyPressed {
+            key: keyboard::Key::Character(chars),
+            physical_key,
+            modifiers,
+            ..
+        }) if !modifiers.command() && !modifiers.alt() => {
+            let mut chars = chars.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(key), None) => Some(Message::QwertyInput {
+                    key: Some(key),
+                    code: physical_key,
+                    stamp: Timestamp::now(),
+                }),
+                _ => None,
+            }
+        }
+
+        // Releases are matched by the physical key only, the character may differ
+        // from the one pressed.
+        iced::Event::Keyboard(keyboard::Event::KeyReleased { physical_key, .. }) => {
+            Some(Message::QwertyInput {
+                key: None,
+                code: physical_key,
+                stamp: Timestamp::now(),
+            })
+        }
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, iced::keyboard::key::Code, wasm_bindgen_test::*};
+
+    fn note_on(key: Key) -> Option<MidiMessage> {
+        Some(MidiMessage::NoteOn {
+            key: key.to_midi(),
+            vel: VELOCITY.into(),
+        })
+    }
+
+    fn note_off(key: Key) -> Option<MidiMessage> {
+        Some(MidiMessage::NoteOff {
+            key: key.to_midi(),
+            vel: 0.into(),
+        })
+    }
+
+    /// Physical key of the character on a US layout.
+    fn code(ch: char) -> Physical {
+        Physical::Code(match ch.to_ascii_lowercase() {
+            'a' => Code::KeyA,
+            'h' => Code::KeyH,
+            'k' => Code::KeyK,
+            'o' => Code::KeyO,
+            'q' => Code::KeyQ,
+            's' => Code::KeyS,
+            'w' => Code::KeyW,
+            'x' => Code::KeyX,
+            'z' => Code::KeyZ,
+            ';' | ':' => Code::Semicolon,
+            '\'' | '"' => Code::Quote,
+            _ => panic!("unmapped key {ch:?}"),
+        })
+    }
+
+    fn press(mapper: &mut Mapper, ch: char) -> Option<MidiMessage> {
+        mapper.key_pressed(ch, code(ch))
+    }
+
+    fn release(mapper: &mut Mapper, ch: char) -> Option<MidiMessage> {
+        mapper.key_released(code(ch))
+    }
+
+    #[wasm_bindgen_test]
+    fn mapping() {
+        let mut mapper = Mapper::new(Mapping::default());
+
+        assert_eq!(press(&mut mapper, 'a'), note_on(KeyPos::C.oct(4)));
+        assert_eq!(press(&mut mapper, 'W'), note_on(KeyPos::CSharp.oct(4)));
+        assert_eq!(press(&mut mapper, 'k'), note_on(KeyPos::C.oct(5)));
+        assert_eq!(press(&mut mapper, '\''), note_on(KeyPos::F.oct(5)));
+        assert_eq!(press(&mut mapper, 'q'), None);
+
+        // Repeated presses are ignored until the key is released.
+        assert_eq!(press(&mut mapper, 'a'), None);
+        assert_eq!(release(&mut mapper, 'a'), note_off(KeyPos::C.oct(4)));
+        assert_eq!(release(&mut mapper, 'a'), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn modifier_change() {
+        let mut mapper = Mapper::new(Mapping::default());
+
+        // Shift is pressed while the key is held, so the character of the
+        // release differs.
+        assert_eq!(press(&mut mapper, ';'), note_on(KeyPos::E.oct(5)));
+        assert_eq!(release(&mut mapper, ':'), note_off(KeyPos::E.oct(5)));
+        assert_eq!(press(&mut mapper, 'a'), note_on(KeyPos::C.oct(4)));
+        assert_eq!(release(&mut mapper, 'A'), note_off(KeyPos::C.oct(4)));
+    }
+
+    #[wasm_bindgen_test]
+    fn octave_shift() {
+        let mut mapper = Mapper::new(Mapping::default());
+
+        assert_eq!(press(&mut mapper, 'a'), note_on(KeyPos::C.oct(4)));
+        assert_eq!(press(&mut mapper, 'x'), None);
+        assert_eq!(mapper.octave(), 5);
+        assert_eq!(press(&mut mapper, 's'), note_on(KeyPos::D.oct(5)));
+
+        // Held keys are released in the octave they were pressed in.
+        assert_eq!(release(&mut mapper, 'a'), note_off(KeyPos::C.oct(4)));
+
+        for _ in 0..10 {
+            press(&mut mapper, 'z');
+        }
+
+        assert_eq!(mapper.octave(), 0);
+        // Keys below A0 are not on the keyboard.
+        assert_eq!(press(&mut mapper, 'a'), None);
+        assert_eq!(press(&mut mapper, 'h'), note_on(KeyPos::A.oct(0)));
+
+        for _ in 0..10 {
+            press(&mut mapper, 'x');
+        }
+
+        assert_eq!(mapper.octave(), MAX_OCTAVE);
+        assert_eq!(press(&mut mapper, 'k'), note_on(KeyPos::C.oct(8)));
+        assert_eq!(press(&mut mapper, 'o'), None);
+    }
+}
@@ -0,0 +1,520 @@
+use {
+    super::{
+        Event,
+        Timestamp,
+        channel::{Channel, Hand},
+        filter_message,
+    },
+    crate::{keyboard::Pitch, util::Hint},
+    midly::{
+        Format,
+        Header,
+        MetaMessage,
+        MidiMessage,
+        Smf,
+        Timing,
+        TrackEvent,
+        TrackEventKind,
+        num::{u15, u24, u28},
+    },
+    std::time::Duration,
+};
+
+/// Resolution of the recorded files. With the default tempo, a tick lasts a
+/// millisecond.
+const TICKS_PER_BEAT: u16 = 500;
+/// Default tempo of midi files, in microseconds per beat (120 bpm).
+const DEFAULT_TEMPO: u32 = 500_000;
+/// Prefix of the markers holding the notes of a challenge, e.g.
+/// `Challenge: C4 Bb3`.
+const CHALLENGE_MARKER: &str = "Challenge: ";
+
+#[derive(Debug, thiserror::Error, Clone)]
+pub enum Error {
+    #[error("Failed to read the MIDI file: {0}")]
+    ParseFailed(String),
+
+    #[error("The MIDI file contains no notes")]
+    NoNotes,
+
+    #[error("Failed to write the MIDI file: {0}")]
+    WriteFailed(String),
+}
+
+impl Hint for Error {
+    fn hint(&self) -> &'static str {
+        match self {
+            Self::ParseFailed(_) => "Make sure the file is a Standard MIDI File (.mid).",
+            Self::NoNotes => "Choose a file with a recorded performance.",
+            Self::WriteFailed(_) => "Try saving the recording again.",
+        }
+    }
+}
+
+/// Note or pedal event of a recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recorded {
+    pub message: MidiMessage,
+    pub channel: Channel,
+    pub hand: Option<Hand>,
+}
+
+/// Records the note and pedal events and the challenges of a session.
+#[derive(Debug, Default, Clone)]
+pub struct Recorder {
+    /// Start of the session, written as the first tick of the file.
+    start: Option<Timestamp>,
+    events: Vec<(Timestamp, Recorded)>,
+    /// Notes of each challenge, with the time it was shown.
+    challenges: Vec<(Timestamp, Vec<Pitch>)>,
+}
+
+impl Recorder {
+    /// Marks the start of the session. Without it, the file starts with the
+    /// first recorded event.
+    pub fn start(&mut self, stamp: Timestamp) {
+        self.start = Some(stamp);
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        if let Some(message) = filter_message(event.message) {
+            self.events.push((event.stamp, Recorded {
+                message,
+                // Events of the on-screen and computer keyboards have no channel.
+                channel: event.channel.unwrap_or(Channel::ALL[0]),
+                hand: event.hand,
+            }));
+        }
+    }
+
+    pub fn record_challenge(&mut self, stamp: Timestamp, pitches: impl IntoIterator<Item = Pitch>) {
+        self.challenges.push((stamp, pitches.into_iter().collect()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Writes the session as a Standard MIDI File. The first track holds the
+    /// tempo and the challenges as markers, the following tracks the events
+    /// of each hand, on their original channels.
+    pub fn to_smf(&self) -> Result<Vec<u8>, Error> {
+        let start = self.start.or_else(|| {
+            let first_event = self.events.first().map(|(stamp, _)| *stamp);
+            let first_challenge = self.challenges.first().map(|(stamp, _)| *stamp);
+            first_event.into_iter().chain(first_challenge).min()
+        });
+
+        let micros_per_tick = (DEFAULT_TEMPO / TICKS_PER_BEAT as u32) as u64;
+        let to_tick = |stamp: Timestamp| {
+            let elapsed = start.map_or(Duration::ZERO, |start| stamp.duration_since(start));
+            elapsed.as_micros() as u64 / micros_per_tick
+        };
+
+        let markers = self
+            .challenges
+            .iter()
+            .map(|(stamp, pitches)| {
+                let pitches = pitches.iter().map(Pitch::to_string).collect::<Vec<_>>();
+                (
+                    to_tick(*stamp),
+                    format!("{CHALLENGE_MARKER}{}", pitches.join(" ")),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let conductor = [(
+            0,
+            TrackEventKind::Meta(MetaMessage::Tempo(u24::new(DEFAULT_TEMPO))),
+        )]
+        .into_iter()
+        .chain(markers.iter().map(|(tick, text)| {
+            (
+                *tick,
+                TrackEventKind::Meta(MetaMessage::Marker(text.as_bytes())),
+            )
+        }));
+
+        let mut tracks = vec![to_track(conductor)];
+
+        for hand in [None, Some(Hand::Right), Some(Hand::Left)] {
+            let mut events = self
+                .events
+                .iter()
+                .filter(|(_, recorded)| recorded.hand == hand)
+                .map(|(stamp, recorded)| {
+                    (to_tick(*stamp), TrackEventKind::Midi {
+                        channel: recorded.channel.to_midi(),
+                        message: recorded.message,
+                    })
+                })
+                .peekable();
+
+            if events.peek().is_none() {
+                continue;
+            }
+
+            let name = track_name(hand);
+            let name = [(
+                0,
+                TrackEventKind::Meta(MetaMessage::TrackName(name.as_bytes())),
+            )];
+
+            tracks.push(to_track(name.into_iter().chain(events)));
+        }
+
+        let smf = Smf {
+            header: Header::new(Format::Parallel, Timing::Metrical(u15::new(TICKS_PER_BEAT))),
+            tracks,
+        };
+
+        let mut buf = Vec::new();
+
+        smf.write_std(&mut buf)
+            .map_err(|err| Error::WriteFailed(err.to_string()))?;
+
+        Ok(buf)
+    }
+}
+
+/// Converts the events with absolute ticks into a track, ending it.
+fn to_track<'a>(
+    events: impl IntoIterator<Item = (u64, TrackEventKind<'a>)>,
+) -> Vec<TrackEvent<'a>> {
+    let mut prev_tick = 0;
+
+    let mut track = events
+        .into_iter()
+        .map(|(tick, kind)| {
+            // Events are recorded in the order they're received, so they're never
+            // earlier than the previous one.
+            let tick = tick.max(prev_tick);
+            let delta = (tick - prev_tick).min(u28::max_value().as_int() as u64) as u32;
+            prev_tick = tick;
+
+            TrackEvent {
+                delta: delta.into(),
+                kind,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    track.push(TrackEvent {
+        delta: 0.into(),
+        kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+    });
+
+    track
+}
+
+fn track_name(hand: Option<Hand>) -> &'static str {
+    match hand {
+        None => "Both hands",
+        Some(Hand::Left) => "Left hand",
+        Some(Hand::Right) => "Right hand",
+    }
+}
+
+/// Note and pedal events of a midi file, to be replayed as input, along with
+/// the recorded challenges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// Name of the file.
+    pub name: String,
+    /// Events with their time since the start of the file, in order.
+    events: Vec<(Duration, Recorded)>,
+    /// Notes of the recorded challenges with their time since the start of
+    /// the file, in order.
+    challenges: Vec<(Duration, Vec<Pitch>)>,
+}
+
+impl Recording {
+    /// Parses a Standard MIDI File. Events of all tracks and channels are
+    /// merged. Events of tracks named after a hand are played by that hand.
+    pub fn parse(name: impl Into<String>, data: &[u8]) -> Result<Self, Error> {
+        let smf = Smf::parse(data).map_err(|err| Error::ParseFailed(err.to_string()))?;
+
+        let mut events = Vec::new();
+        let mut challenges = Vec::new();
+        let mut tempos = Vec::new();
+
+        for track in &smf.tracks {
+            let mut tick = 0;
+            let mut hand = None;
+
+            for event in track {
+                tick += event.delta.as_int() as u64;
+
+                match event.kind {
+                    TrackEventKind::Midi { channel, message } => {
+                        if let Some(message) = filter_message(message) {
+                            events.push((tick, Recorded {
+                                message,
+                                channel: Channel::from_midi(channel),
+                                hand,
+                            }));
+                        }
+                    }
+
+                    TrackEventKind::Meta(MetaMessage::TrackName(name)) => {
+                        hand = [Hand::Right, Hand::Left]
+                            .into_iter()
+                            .find(|hand| name == track_name(Some(*hand)).as_bytes());
+                    }
+
+                    TrackEventKind::Meta(MetaMessage::Marker(text)) => {
+                        if let Some(pitches) = parse_challenge(text) {
+                            challenges.push((tick, pitches));
+                        }
+                    }
+
+                    TrackEventKind::Meta(MetaMessage::Tempo(tempo)) => {
+                        tempos.push((tick, tempo.as_int()));
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        if events.is_empty() {
+            return Err(Error::NoNotes);
+        }
+
+        // The sort is stable, so simultaneous events keep their order.
+        events.sort_by_key(|(tick, _)| *tick);
+        challenges.sort_by_key(|(tick, _)| *tick);
+        tempos.sort_by_key(|(tick, _)| *tick);
+
+        let to_duration = |tick: u64| {
+            let micros = match smf.header.timing {
+                Timing::Metrical(ticks_per_beat) => {
+                    metrical_to_micros(tick, ticks_per_beat.as_int() as u64, &tempos)
+                }
+
+                Timing::Timecode(fps, subframes) => {
+                    (tick as f64 * 1_000_000. / (fps.as_f32() as f64 * subframes as f64)) as u64
+                }
+            };
+
+            Duration::from_micros(micros)
+        };
+
+        Ok(Self {
+            name: name.into(),
+            events: events
+                .into_iter()
+                .map(|(tick, recorded)| (to_duration(tick), recorded))
+                .collect(),
+            challenges: challenges
+                .into_iter()
+                .map(|(tick, pitches)| (to_duration(tick), pitches))
+                .collect(),
+        })
+    }
+
+    pub fn events(&self) -> &[(Duration, Recorded)] {
+        &self.events
+    }
+
+    pub fn challenges(&self) -> &[(Duration, Vec<Pitch>)] {
+        &self.challenges
+    }
+}
+
+/// Parses the notes of a challenge marker, e.g. `Challenge: C4 Bb3`.
+fn parse_challenge(text: &[u8]) -> Option<Vec<Pitch>> {
+    std::str::from_utf8(text)
+        .ok()?
+        .strip_prefix(CHALLENGE_MARKER)?
+        .split_whitespace()
+        .map(|pitch| pitch.parse().ok())
+        .collect()
+}
+
+/// Converts the absolute tick to microseconds, following the tempo changes.
+fn metrical_to_micros(tick: u64, ticks_per_beat: u64, tempos: &[(u64, u32)]) -> u64 {
+    let mut micros = 0;
+    let mut last_tick = 0;
+    let mut tempo = DEFAULT_TEMPO as u64;
+
+    for (at, new_tempo) in tempos.iter().take_while(|(at, _)| *at <= tick) {
+        micros += (at - last_tick) * tempo / ticks_per_beat;
+        last_tick = *at;
+        tempo = *new_tempo as u64;
+    }
+
+    micros + (tick - last_tick) * tempo / ticks_per_beat
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            input::fixtures::{note_off, note_on_with_velocity},
+            keyboard::{Accidental, Letter},
+        },
+        wasm_bindgen_test::*,
+    };
+
+    fn event(micros: u64, message: MidiMessage) -> Event {
+        Event::new(message, Timestamp::from_micros(micros))
+    }
+
+    fn midi(delta: u32, message: MidiMessage) -> TrackEvent<'static> {
+        TrackEvent {
+            delta: delta.into(),
+            kind: TrackEventKind::Midi {
+                channel: 0.into(),
+                message,
+            },
+        }
+    }
+
+    fn write(smf: &Smf) -> Vec<u8> {
+        let mut buf = Vec::new();
+        smf.write_std(&mut buf).unwrap();
+        buf
+    }
+
+    /// Returns the recorded messages with their time, ignoring the channels.
+    fn messages(recording: &Recording) -> Vec<(Duration, MidiMessage)> {
+        recording
+            .events()
+            .iter()
+            .map(|(offset, recorded)| (*offset, recorded.message))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trip() {
+        let mut recorder = Recorder::default();
+        assert!(recorder.is_empty());
+
+        recorder.record(&event(5_000_000, note_on_with_velocity(60, 80)));
+        recorder.record(&event(5_250_400, note_on_with_velocity(64, 70)));
+        recorder.record(&event(5_250_400, note_off(60)));
+        recorder.record(&event(6_000_000, MidiMessage::Controller {
+            controller: 64.into(),
+            value: 127.into(),
+        }));
+        // Other controllers are not recorded.
+        recorder.record(&event(6_100_000, MidiMessage::Controller {
+            controller: 7.into(),
+            value: 100.into(),
+        }));
+
+        let recording = Recording::parse("session.mid", &recorder.to_smf().unwrap()).unwrap();
+
+        // Without a start, the file starts with the first event.
+        assert_eq!(recording.name, "session.mid");
+        assert_eq!(messages(&recording), [
+            (Duration::ZERO, note_on_with_velocity(60, 80)),
+            (Duration::from_millis(250), note_on_with_velocity(64, 70)),
+            (Duration::from_millis(250), note_off(60)),
+            (Duration::from_secs(1), MidiMessage::Controller {
+                controller: 64.into(),
+                value: 127.into(),
+            }),
+        ]);
+        assert_eq!(recording.challenges(), []);
+    }
+
+    #[wasm_bindgen_test]
+    fn session() {
+        let mut recorder = Recorder::default();
+        let c4 = Pitch::new(Letter::C, Accidental::Natural, 4);
+        let b_flat3 = Pitch::new(Letter::B, Accidental::Flat, 3);
+
+        recorder.start(Timestamp::from_micros(1_000_000));
+        recorder.record_challenge(Timestamp::from_micros(1_000_000), [c4, b_flat3]);
+        recorder.record(&Event {
+            message: note_on_with_velocity(60, 64),
+            hand: Some(Hand::Right),
+            channel: Some(Channel::ALL[1]),
+            port: None,
+            stamp: Timestamp::from_micros(1_500_000),
+        });
+        recorder.record(&Event {
+            message: note_on_with_velocity(58, 64),
+            hand: Some(Hand::Left),
+            channel: Some(Channel::ALL[2]),
+            port: None,
+            stamp: Timestamp::from_micros(1_600_000),
+        });
+        recorder.record(&event(1_700_000, note_off(60)));
+        recorder.record_challenge(Timestamp::from_micros(2_000_000), [c4]);
+
+        let recording = Recording::parse("session.mid", &recorder.to_smf().unwrap()).unwrap();
+
+        assert_eq!(recording.events(), [
+            (Duration::from_millis(500), Recorded {
+                message: note_on_with_velocity(60, 64),
+                channel: Channel::ALL[1],
+                hand: Some(Hand::Right),
+            }),
+            (Duration::from_millis(600), Recorded {
+                message: note_on_with_velocity(58, 64),
+                channel: Channel::ALL[2],
+                hand: Some(Hand::Left),
+            }),
+            (Duration::from_millis(700), Recorded {
+                message: note_off(60),
+                channel: Channel::ALL[0],
+                hand: None,
+            }),
+        ]);
+        assert_eq!(recording.challenges(), [
+            (Duration::ZERO, vec![c4, b_flat3]),
+            (Duration::from_secs(1), vec![c4]),
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    fn tempo_changes() {
+        let tempo = |delta: u32, tempo: u32| TrackEvent {
+            delta: delta.into(),
+            kind: TrackEventKind::Meta(MetaMessage::Tempo(tempo.into())),
+        };
+
+        // Tempo track and note track, as written by most sequencers.
+        let smf = Smf {
+            header: Header::new(Format::Parallel, Timing::Metrical(96.into())),
+            tracks: vec![vec![tempo(0, 1_000_000), tempo(192, 250_000)], vec![
+                midi(96, note_on_with_velocity(60, 64)),
+                midi(96, note_on_with_velocity(60, 0)),
+                midi(96, note_on_with_velocity(62, 64)),
+            ]],
+        };
+
+        let recording = Recording::parse("song.mid", &write(&smf)).unwrap();
+
+        // A note on with zero velocity is a note off.
+        assert_eq!(messages(&recording), [
+            (Duration::from_secs(1), note_on_with_velocity(60, 64)),
+            (Duration::from_secs(2), note_off(60)),
+            (Duration::from_millis(2250), note_on_with_velocity(62, 64)),
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_files() {
+        assert!(matches!(
+            Recording::parse("notes.txt", b"C D E"),
+            Err(Error::ParseFailed(_))
+        ));
+
+        let smf = Smf {
+            header: Header::new(Format::SingleTrack, Timing::Metrical(96.into())),
+            tracks: vec![vec![midi(0, MidiMessage::ProgramChange {
+                program: 1.into(),
+            })]],
+        };
+
+        assert!(matches!(
+            Recording::parse("empty.mid", &write(&smf)),
+            Err(Error::NoNotes)
+        ));
+    }
+}
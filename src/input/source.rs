@@ -0,0 +1,195 @@
+use {
+    super::{
+        Error,
+        PortId,
+        Timestamp,
+        channel::{Channel, Hand},
+    },
+    futures_util::{
+        Stream,
+        StreamExt as _,
+        stream::{self, AbortHandle, LocalBoxStream},
+    },
+    midly::MidiMessage,
+};
+
+/// Note or pedal event produced by an input source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub message: MidiMessage,
+    /// Hand the event is played with, if the channel of the device is mapped
+    /// to a hand.
+    pub hand: Option<Hand>,
+    /// Channel the event is received on, if the source has channels.
+    pub channel: Option<Channel>,
+    /// Midi port the event is received from, if the source is a midi device.
+    pub port: Option<PortId>,
+    pub stamp: Timestamp,
+}
+
+impl Event {
+    pub fn new(message: MidiMessage, stamp: Timestamp) -> Self {
+        Self {
+            message,
+            hand: None,
+            channel: None,
+            port: None,
+            stamp,
+        }
+    }
+}
+
+pub type EventStream = LocalBoxStream<'static, Event>;
+
+/// Source of input events, e.g. a midi device or a replayed midi file. Events
+/// of all sources are merged and handled the same way by the game.
+pub trait InputSource {
+    /// Connects to the source, returning the stream of its events. Connecting
+    /// again replaces the previous stream.
+    fn connect(&mut self) -> Result<EventStream, Error>;
+
+    /// Disconnects from the source, ending the stream of its events.
+    fn disconnect(&mut self);
+}
+
+/// Makes the stream end once the source is disconnected with the abort handle.
+/// The handle of the previous stream is aborted.
+pub fn abortable(
+    events: impl Stream<Item = Event> + 'static,
+    handle: &mut Option<AbortHandle>,
+) -> EventStream {
+    let (events, abort) = stream::abortable(events);
+
+    if let Some(prev) = handle.replace(abort) {
+        prev.abort();
+    }
+
+    events.boxed_local()
+}
+
+/// Source producing a scripted sequence of events, to exercise the game without
+/// a device in tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct FakeSource {
+    events: Vec<Event>,
+    abort: Option<AbortHandle>,
+}
+
+#[cfg(test)]
+impl FakeSource {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+            abort: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl InputSource for FakeSource {
+    fn connect(&mut self) -> Result<EventStream, Error> {
+        Ok(abortable(
+            stream::iter(self.events.clone()),
+            &mut self.abort,
+        ))
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(abort) = self.abort.take() {
+            abort.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::input::{
+            fixtures::{note_off, note_on},
+            pedal::{self, Pedal},
+        },
+        futures_util::FutureExt as _,
+        wasm_bindgen_test::*,
+    };
+
+    fn event(message: MidiMessage, micros: u64) -> Event {
+        Event::new(message, Timestamp::from_micros(micros))
+    }
+
+    fn sustain(value: u8) -> MidiMessage {
+        MidiMessage::Controller {
+            controller: 64.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Returns the events which are ready, without waiting for more.
+    fn ready(events: &mut EventStream) -> Vec<Event> {
+        std::iter::from_fn(|| events.next().now_or_never().flatten()).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn fake_source() {
+        let script = [event(note_on(60), 1000), event(note_off(60), 2000)];
+        let mut source = FakeSource::new(script);
+
+        let mut events = source.connect().unwrap();
+        assert_eq!(ready(&mut events), script);
+
+        // Connecting again replays the script.
+        let mut events = source.connect().unwrap();
+        assert_eq!(ready(&mut events)[0], script[0]);
+    }
+
+    #[wasm_bindgen_test]
+    fn disconnect() {
+        let mut source = FakeSource::new([event(note_on(60), 1000), event(note_on(62), 2000)]);
+        let mut events = source.connect().unwrap();
+
+        assert_eq!(
+            events.next().now_or_never(),
+            Some(Some(event(note_on(60), 1000)))
+        );
+
+        source.disconnect();
+        assert_eq!(events.next().now_or_never(), Some(None));
+
+        // The previous stream ends once the source is connected again.
+        let mut prev = source.connect().unwrap();
+        let _events = source.connect().unwrap();
+        assert_eq!(prev.next().now_or_never(), Some(None));
+    }
+
+    #[wasm_bindgen_test]
+    fn pipeline() {
+        // Events of the source go through the pedal tracker, like in the game.
+        let mut source = FakeSource::new([
+            event(note_on(60), 1000),
+            event(sustain(127), 1500),
+            event(note_off(60), 2000),
+            event(sustain(0), 3000),
+        ]);
+
+        let mut tracker = pedal::Tracker::default();
+
+        let processed = ready(&mut source.connect().unwrap())
+            .into_iter()
+            .flat_map(|event| tracker.process(event.message))
+            .collect::<Vec<_>>();
+
+        assert_eq!(processed, [
+            pedal::Event::Note(note_on(60)),
+            pedal::Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: true
+            },
+            pedal::Event::Pedal {
+                pedal: Pedal::Sustain,
+                pressed: false
+            },
+            pedal::Event::Note(note_off(60)),
+        ]);
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+/// Monotonic time of an input event, in microseconds. In the browser, uses the
+/// clock of `performance.now()`, which is also the clock of the Web MIDI event
+/// timestamps, so that events from all input devices can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub fn from_micros(micros: u64) -> Self {
+        Self(micros)
+    }
+
+    /// Returns the current time, for events which don't carry a timestamp of
+    /// their own.
+    pub fn now() -> Self {
+        Self((instant::now() * 1000.) as u64)
+    }
+
+    pub fn as_micros(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the time elapsed since the earlier timestamp, or zero if the
+    /// timestamp is actually later.
+    pub fn duration_since(self, earlier: Self) -> Duration {
+        Duration::from_micros(self.0.saturating_sub(earlier.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, wasm_bindgen_test::*};
+
+    #[wasm_bindgen_test]
+    fn duration() {
+        let shown = Timestamp::from_micros(1_000_000);
+        let played = Timestamp::from_micros(1_843_250);
+
+        assert_eq!(played.duration_since(shown), Duration::from_micros(843_250));
+        assert_eq!(shown.duration_since(played), Duration::ZERO);
+        assert!(shown < played);
+    }
+}
@@ -1,4 +1,9 @@
-use {derive_more::Display, midly::num::u7, std::ops::RangeInclusive};
+use {
+    derive_more::Display,
+    midly::num::u7,
+    serde::{Deserialize, Deserializer, Serialize, Serializer, de},
+    std::{fmt, ops::RangeInclusive, str::FromStr},
+};
 
 const KEY_RANGE_88: RangeInclusive<u8> = 21..=108;
 
@@ -6,6 +11,24 @@ const KEY_RANGE_88: RangeInclusive<u8> = 21..=108;
 pub enum Error {
     #[error("Key out of range")]
     KeyOutOfRange,
+
+    #[error("Invalid key signature")]
+    InvalidKeySignature,
+
+    #[error("Invalid keyboard range")]
+    InvalidKeyboardRange,
+
+    #[error("Invalid key range")]
+    InvalidKeyRange,
+
+    #[error("Invalid interval")]
+    InvalidInterval,
+
+    #[error("Accidental out of range")]
+    AccidentalOutOfRange,
+
+    #[error("Failed to parse pitch: {0}")]
+    InvalidPitch(String),
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -76,14 +99,20 @@ impl KeyPos {
     }
 
     pub fn pitch_name(&self) -> &'static str {
+        self.letter().pitch_name()
+    }
+
+    /// Returns the letter of the natural key, or of the natural key directly
+    /// below for sharp keys.
+    pub fn letter(&self) -> Letter {
         match self {
-            Self::C | Self::CSharp => "c",
-            Self::D | Self::DSharp => "d",
-            Self::E => "e",
-            Self::F | Self::FSharp => "f",
-            Self::G | Self::GSharp => "g",
-            Self::A | Self::ASharp => "a",
-            Self::B => "b",
+            Self::C | Self::CSharp => Letter::C,
+            Self::D | Self::DSharp => Letter::D,
+            Self::E => Letter::E,
+            Self::F | Self::FSharp => Letter::F,
+            Self::G | Self::GSharp => Letter::G,
+            Self::A | Self::ASharp => Letter::A,
+            Self::B => Letter::B,
         }
     }
 
@@ -106,8 +135,661 @@ impl KeyPos {
     }
 }
 
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Letter {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Letter {
+    pub const ALL: [Self; 7] = [
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::A,
+        Self::B,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::C => "C",
+            Self::D => "D",
+            Self::E => "E",
+            Self::F => "F",
+            Self::G => "G",
+            Self::A => "A",
+            Self::B => "B",
+        }
+    }
+
+    pub fn pitch_name(&self) -> &'static str {
+        match self {
+            Self::C => "c",
+            Self::D => "d",
+            Self::E => "e",
+            Self::F => "f",
+            Self::G => "g",
+            Self::A => "a",
+            Self::B => "b",
+        }
+    }
+
+    /// Position of the natural key with this letter.
+    pub fn natural(&self) -> KeyPos {
+        match self {
+            Self::C => KeyPos::C,
+            Self::D => KeyPos::D,
+            Self::E => KeyPos::E,
+            Self::F => KeyPos::F,
+            Self::G => KeyPos::G,
+            Self::A => KeyPos::A,
+            Self::B => KeyPos::B,
+        }
+    }
+
+    pub fn idx(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn from_idx(idx: usize) -> Self {
+        Self::ALL[idx % Self::ALL.len()]
+    }
+}
+
+#[derive(
+    Default, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Accidental {
+    DoubleFlat,
+    Flat,
+    #[default]
+    Natural,
+    Sharp,
+    DoubleSharp,
+}
+
+impl Accidental {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DoubleFlat => "bb",
+            Self::Flat => "b",
+            Self::Natural => "",
+            Self::Sharp => "#",
+            Self::DoubleSharp => "x",
+        }
+    }
+
+    /// Number of semitones this accidental shifts the natural pitch by.
+    pub fn offset(&self) -> i8 {
+        match self {
+            Self::DoubleFlat => -2,
+            Self::Flat => -1,
+            Self::Natural => 0,
+            Self::Sharp => 1,
+            Self::DoubleSharp => 2,
+        }
+    }
+
+    pub fn from_offset(offset: i8) -> Option<Self> {
+        match offset {
+            -2 => Some(Self::DoubleFlat),
+            -1 => Some(Self::Flat),
+            0 => Some(Self::Natural),
+            1 => Some(Self::Sharp),
+            2 => Some(Self::DoubleSharp),
+            _ => None,
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.offset() < 0
+    }
+
+    pub fn is_sharp(&self) -> bool {
+        self.offset() > 0
+    }
+}
+
+/// Spelled pitch without an octave, e.g. `F#` or `Bb`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PitchClass {
+    pub letter: Letter,
+    pub accidental: Accidental,
+}
+
+impl PitchClass {
+    pub const fn new(letter: Letter, accidental: Accidental) -> Self {
+        Self { letter, accidental }
+    }
+
+    pub const fn oct(self, oct: u8) -> Pitch {
+        Pitch::new(self.letter, self.accidental, oct)
+    }
+}
+
+impl fmt::Display for PitchClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.letter.as_str(), self.accidental.as_str())
+    }
+}
+
+/// Spelled pitch, i.e. a letter with an accidental in a specific octave, as it
+/// appears on the sheet. Multiple spellings may map onto the same physical
+/// [`Key`] (e.g. `C#4` and `Db4`).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Pitch {
+    pub letter: Letter,
+    pub accidental: Accidental,
+    /// Written octave, which may differ from the physical key octave (e.g.
+    /// `Cb4` is played as `B3`).
+    pub oct: u8,
+}
+
+impl Pitch {
+    pub const fn new(letter: Letter, accidental: Accidental, oct: u8) -> Self {
+        Self {
+            letter,
+            accidental,
+            oct,
+        }
+    }
+
+    pub fn class(&self) -> PitchClass {
+        PitchClass::new(self.letter, self.accidental)
+    }
+
+    /// Returns the physical key this pitch is played with.
+    pub fn to_key(&self) -> Result<Key, Error> {
+        let midi = Key::OFFSET as i16 + self.semitones();
+
+        u8::try_from(midi)
+            .ok()
+            .filter(|midi| *midi <= 127)
+            .ok_or(Error::KeyOutOfRange)
+            .and_then(|midi| Key::try_from_midi(midi.into()))
+    }
+
+    /// Transposes the pitch up by the interval, keeping the spelling implied by
+    /// the interval number (e.g. `C4` up a minor third is `Eb4`, not `D#4`).
+    pub fn transpose(&self, interval: Interval) -> Result<Self, Error> {
+        let steps = self.steps() + interval.number as i16 - 1;
+        let letter = Letter::from_idx(steps.rem_euclid(7) as usize);
+        let oct = u8::try_from(steps.div_euclid(7)).map_err(|_| Error::KeyOutOfRange)?;
+        let natural = Self::new(letter, Accidental::Natural, oct);
+        let offset = self.semitones() + interval.semitones() - natural.semitones();
+
+        i8::try_from(offset)
+            .ok()
+            .and_then(Accidental::from_offset)
+            .map(|accidental| Self::new(letter, accidental, oct))
+            .ok_or(Error::AccidentalOutOfRange)
+    }
+
+    /// Number of semitones from `C0`.
+    fn semitones(&self) -> i16 {
+        self.oct as i16 * 12 + self.letter.natural() as i16 + self.accidental.offset() as i16
+    }
+
+    /// Number of staff steps (letters) from `C0`.
+    fn steps(&self) -> i16 {
+        self.oct as i16 * 7 + self.letter.idx() as i16
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.letter.as_str(),
+            self.accidental.as_str(),
+            self.oct
+        )
+    }
+}
+
+impl From<Key> for Pitch {
+    fn from(key: Key) -> Self {
+        key.spell(Accidental::Sharp)
+    }
+}
+
+/// Parses pitches in scientific pitch notation, e.g. `C4`, `C#4`, `Bb3` or
+/// `Fx5`.
+impl FromStr for Pitch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::InvalidPitch(s.to_owned());
+        let mut chars = s.trim().chars();
+
+        let letter = match chars.next().map(|ch| ch.to_ascii_uppercase()) {
+            Some('C') => Letter::C,
+            Some('D') => Letter::D,
+            Some('E') => Letter::E,
+            Some('F') => Letter::F,
+            Some('G') => Letter::G,
+            Some('A') => Letter::A,
+            Some('B') => Letter::B,
+            _ => return Err(err()),
+        };
+
+        let rest = chars.as_str();
+        let oct_idx = rest.find(|ch: char| ch.is_ascii_digit()).ok_or_else(err)?;
+        let (accidental, oct) = rest.split_at(oct_idx);
+
+        let accidental = match accidental {
+            "bb" | "\u{266d}\u{266d}" | "\u{1d12b}" => Accidental::DoubleFlat,
+            "b" | "\u{266d}" => Accidental::Flat,
+            "" => Accidental::Natural,
+            "#" | "\u{266f}" => Accidental::Sharp,
+            "x" | "##" | "\u{266f}\u{266f}" | "\u{1d12a}" => Accidental::DoubleSharp,
+            _ => return Err(err()),
+        };
+
+        let oct = oct.parse().map_err(|_| err())?;
+
+        Ok(Self::new(letter, accidental, oct))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Quality {
+    Diminished,
+    Minor,
+    Perfect,
+    Major,
+    Augmented,
+}
+
+impl Quality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Diminished => "d",
+            Self::Minor => "m",
+            Self::Perfect => "P",
+            Self::Major => "M",
+            Self::Augmented => "A",
+        }
+    }
+}
+
+/// Interval between two pitches, e.g. a major third (`M3`) or a perfect fifth
+/// (`P5`). Numbers above 8 describe compound intervals.
+#[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[display("{}{}", quality.as_str(), number)]
+pub struct Interval {
+    pub quality: Quality,
+    pub number: u8,
+}
+
+impl Interval {
+    pub const UNISON: Self = Self::new_unchecked(Quality::Perfect, 1);
+    pub const AUGMENTED_UNISON: Self = Self::new_unchecked(Quality::Augmented, 1);
+    pub const MINOR_SECOND: Self = Self::new_unchecked(Quality::Minor, 2);
+    pub const MAJOR_SECOND: Self = Self::new_unchecked(Quality::Major, 2);
+    pub const AUGMENTED_SECOND: Self = Self::new_unchecked(Quality::Augmented, 2);
+    pub const MINOR_THIRD: Self = Self::new_unchecked(Quality::Minor, 3);
+    pub const MAJOR_THIRD: Self = Self::new_unchecked(Quality::Major, 3);
+    pub const PERFECT_FOURTH: Self = Self::new_unchecked(Quality::Perfect, 4);
+    pub const AUGMENTED_FOURTH: Self = Self::new_unchecked(Quality::Augmented, 4);
+    pub const DIMINISHED_FIFTH: Self = Self::new_unchecked(Quality::Diminished, 5);
+    pub const PERFECT_FIFTH: Self = Self::new_unchecked(Quality::Perfect, 5);
+    pub const AUGMENTED_FIFTH: Self = Self::new_unchecked(Quality::Augmented, 5);
+    pub const MINOR_SIXTH: Self = Self::new_unchecked(Quality::Minor, 6);
+    pub const MAJOR_SIXTH: Self = Self::new_unchecked(Quality::Major, 6);
+    pub const AUGMENTED_SIXTH: Self = Self::new_unchecked(Quality::Augmented, 6);
+    pub const DIMINISHED_SEVENTH: Self = Self::new_unchecked(Quality::Diminished, 7);
+    pub const MINOR_SEVENTH: Self = Self::new_unchecked(Quality::Minor, 7);
+    pub const MAJOR_SEVENTH: Self = Self::new_unchecked(Quality::Major, 7);
+    pub const OCTAVE: Self = Self::new_unchecked(Quality::Perfect, 8);
+
+    /// Largest interval number, a triple octave.
+    pub const MAX_NUMBER: u8 = 22;
+
+    // Semitones of the perfect or major intervals within an octave.
+    const SEMITONES: [i16; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+    const fn new_unchecked(quality: Quality, number: u8) -> Self {
+        Self { quality, number }
+    }
+
+    pub fn new(quality: Quality, number: u8) -> Result<Self, Error> {
+        let interval = Self::new_unchecked(quality, number);
+
+        let is_valid = match quality {
+            _ if number == 0 || number > Self::MAX_NUMBER => false,
+            // Diminished unison would be a negative interval.
+            Quality::Diminished => number != 1,
+            Quality::Perfect => interval.is_perfect(),
+            Quality::Minor | Quality::Major => !interval.is_perfect(),
+            Quality::Augmented => true,
+        };
+
+        if is_valid {
+            Ok(interval)
+        } else {
+            Err(Error::InvalidInterval)
+        }
+    }
+
+    /// Returns the interval between two pitches, or `None` if `upper` is below
+    /// `lower` or the interval can't be described by a simple quality.
+    pub fn between(lower: &Pitch, upper: &Pitch) -> Option<Self> {
+        let number = u8::try_from(upper.steps() - lower.steps() + 1)
+            .ok()
+            .filter(|number| (1..=Self::MAX_NUMBER).contains(number))?;
+        let generic = Self::new_unchecked(Quality::Perfect, number);
+        let semitones = upper.semitones() - lower.semitones();
+
+        let quality = match (semitones - generic.base_semitones(), generic.is_perfect()) {
+            (-1, true) | (-2, false) => Quality::Diminished,
+            (-1, false) => Quality::Minor,
+            (0, true) => Quality::Perfect,
+            (0, false) => Quality::Major,
+            (1, _) => Quality::Augmented,
+            _ => return None,
+        };
+
+        Self::new(quality, number).ok()
+    }
+
+    /// Whether the interval is a unison, fourth, fifth or octave (or their
+    /// compounds), which are perfect rather than major or minor.
+    pub fn is_perfect(&self) -> bool {
+        matches!((self.number - 1) % 7, 0 | 3 | 4)
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    /// Number of semitones spanned by the interval.
+    pub fn semitones(&self) -> i16 {
+        let base = self.base_semitones();
+
+        match self.quality {
+            Quality::Diminished if self.is_perfect() => base - 1,
+            Quality::Diminished => base - 2,
+            Quality::Minor => base - 1,
+            Quality::Perfect | Quality::Major => base,
+            Quality::Augmented => base + 1,
+        }
+    }
+
+    /// Semitones of the perfect or major interval with the same number.
+    fn base_semitones(&self) -> i16 {
+        let steps = self.number as i16 - 1;
+        Self::SEMITONES[steps as usize % 7] + steps / 7 * 12
+    }
+}
+
+#[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ChordKind {
+    #[display("major")]
+    Major,
+
+    #[display("minor")]
+    Minor,
+
+    #[display("diminished")]
+    Diminished,
+
+    #[display("augmented")]
+    Augmented,
+
+    #[display("major seventh")]
+    MajorSeventh,
+
+    #[display("dominant seventh")]
+    DominantSeventh,
+
+    #[display("minor seventh")]
+    MinorSeventh,
+
+    #[display("half-diminished seventh")]
+    HalfDiminishedSeventh,
+
+    #[display("diminished seventh")]
+    DiminishedSeventh,
+}
+
+impl ChordKind {
+    pub const TRIADS: [Self; 4] = [Self::Major, Self::Minor, Self::Diminished, Self::Augmented];
+
+    pub const SEVENTHS: [Self; 5] = [
+        Self::MajorSeventh,
+        Self::DominantSeventh,
+        Self::MinorSeventh,
+        Self::HalfDiminishedSeventh,
+        Self::DiminishedSeventh,
+    ];
+
+    /// Intervals of the chord tones above the root.
+    pub fn intervals(&self) -> &'static [Interval] {
+        match self {
+            Self::Major => &[Interval::MAJOR_THIRD, Interval::PERFECT_FIFTH],
+            Self::Minor => &[Interval::MINOR_THIRD, Interval::PERFECT_FIFTH],
+            Self::Diminished => &[Interval::MINOR_THIRD, Interval::DIMINISHED_FIFTH],
+            Self::Augmented => &[Interval::MAJOR_THIRD, Interval::AUGMENTED_FIFTH],
+            Self::MajorSeventh => &[
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MAJOR_SEVENTH,
+            ],
+            Self::DominantSeventh => &[
+                Interval::MAJOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            Self::MinorSeventh => &[
+                Interval::MINOR_THIRD,
+                Interval::PERFECT_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            Self::HalfDiminishedSeventh => &[
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Interval::MINOR_SEVENTH,
+            ],
+            Self::DiminishedSeventh => &[
+                Interval::MINOR_THIRD,
+                Interval::DIMINISHED_FIFTH,
+                Interval::DIMINISHED_SEVENTH,
+            ],
+        }
+    }
+
+    pub fn num_notes(&self) -> usize {
+        self.intervals().len() + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Chord {
+    pub root: Pitch,
+    pub kind: ChordKind,
+    /// Number of the lowest chord tones moved an octave up, i.e. `0` is the
+    /// root position, `1` is the first inversion etc.
+    pub inversion: u8,
+}
+
+impl Chord {
+    pub const fn new(root: Pitch, kind: ChordKind) -> Self {
+        Self {
+            root,
+            kind,
+            inversion: 0,
+        }
+    }
+
+    pub fn inversion(self, inversion: u8) -> Self {
+        Self { inversion, ..self }
+    }
+
+    /// Returns the spelled chord tones in ascending order.
+    pub fn pitches(&self) -> Result<Vec<Pitch>, Error> {
+        if self.inversion as usize >= self.kind.num_notes() {
+            return Err(Error::InvalidInterval);
+        }
+
+        let mut pitches = [Interval::UNISON]
+            .iter()
+            .chain(self.kind.intervals())
+            .map(|interval| self.root.transpose(*interval))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for pitch in &mut pitches[..self.inversion as usize] {
+            *pitch = pitch.transpose(Interval::OCTAVE)?;
+        }
+
+        pitches.rotate_left(self.inversion as usize);
+
+        Ok(pitches)
+    }
+
+    /// Returns the physical keys of the chord tones in ascending order.
+    pub fn keys(&self) -> Result<Vec<Key>, Error> {
+        self.pitches()?.iter().map(Pitch::to_key).collect()
+    }
+}
+
+/// Major key signature, represented by its position in the circle of fifths:
+/// positive values are the number of sharps, negative values are the number of
+/// flats.
+#[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "i8", into = "i8")]
+pub struct KeySignature(i8);
+
+impl KeySignature {
+    /// All key signatures, starting with sharp keys in the order of the circle
+    /// of fifths, followed by flat keys.
+    pub const ALL: [Self; 15] = [
+        Self(0),
+        Self(1),
+        Self(2),
+        Self(3),
+        Self(4),
+        Self(5),
+        Self(6),
+        Self(7),
+        Self(-1),
+        Self(-2),
+        Self(-3),
+        Self(-4),
+        Self(-5),
+        Self(-6),
+        Self(-7),
+    ];
+
+    const ORDER_OF_SHARPS: [Letter; 7] = [
+        Letter::F,
+        Letter::C,
+        Letter::G,
+        Letter::D,
+        Letter::A,
+        Letter::E,
+        Letter::B,
+    ];
+
+    pub fn fifths(&self) -> i8 {
+        self.0
+    }
+
+    /// Returns the accidental implied by the key signature for the letter.
+    pub fn accidental(&self, letter: Letter) -> Accidental {
+        let num = self.0.unsigned_abs() as usize;
+
+        if self.0 > 0 && Self::ORDER_OF_SHARPS[..num].contains(&letter) {
+            Accidental::Sharp
+        } else if self.0 < 0 && Self::ORDER_OF_SHARPS[7 - num..].contains(&letter) {
+            Accidental::Flat
+        } else {
+            Accidental::Natural
+        }
+    }
+
+    /// Returns the tonic of the major key with this signature.
+    pub fn tonic(&self) -> PitchClass {
+        // Each fifth is 4 letters up.
+        let letter = Letter::from_idx((self.0 as isize * 4).rem_euclid(7) as usize);
+        PitchClass::new(letter, self.accidental(letter))
+    }
+
+    /// Returns the key signature of the major key with the tonic, if there is
+    /// one with at most 7 sharps or flats.
+    pub fn from_tonic(tonic: PitchClass) -> Option<Self> {
+        Self::ALL.into_iter().find(|sig| sig.tonic() == tonic)
+    }
+
+    /// Returns the diatonic spelling of the key, or `None` if the key doesn't
+    /// belong to the scale.
+    pub fn spell(&self, key: Key) -> Option<Pitch> {
+        Letter::ALL.into_iter().find_map(|letter| {
+            let accidental = self.accidental(letter);
+            let pos = letter.natural() as i16 + accidental.offset() as i16;
+            let key_pos = key.oct as i16 * 12 + key.pos as i16;
+
+            if (key_pos - pos).rem_euclid(12) == 0 {
+                u8::try_from((key_pos - pos) / 12)
+                    .ok()
+                    .map(|oct| Pitch::new(letter, accidental, oct))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Spells the key diatonically if possible. Otherwise falls back to flats
+    /// for flat key signatures and sharps for the rest.
+    pub fn spell_chromatic(&self, key: Key) -> Pitch {
+        self.spell(key).unwrap_or_else(|| {
+            key.spell(if self.0 < 0 {
+                Accidental::Flat
+            } else {
+                Accidental::Sharp
+            })
+        })
+    }
+
+    pub fn is_diatonic(&self, key: Key) -> bool {
+        self.spell(key).is_some()
+    }
+}
+
+impl fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} major", self.tonic())
+    }
+}
+
+impl TryFrom<i8> for KeySignature {
+    type Error = Error;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        if (-7..=7).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(Error::InvalidKeySignature)
+        }
+    }
+}
+
+impl From<KeySignature> for i8 {
+    fn from(value: KeySignature) -> Self {
+        value.0
+    }
+}
+
 #[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq)]
-#[display("{}/{}", pos.as_str(), oct)]
+#[display("{}", Pitch::from(*self))]
 pub struct Key {
     pub pos: KeyPos,
     pub oct: u8,
@@ -129,6 +811,50 @@ impl Key {
         self.pos.is_sharp()
     }
 
+    /// Spells the key using natural for natural keys, and the preferred
+    /// direction of `accidental` for sharp keys.
+    pub fn spell(&self, accidental: Accidental) -> Pitch {
+        let letter = self.pos.letter();
+
+        if self.is_natural() {
+            Pitch::new(letter, Accidental::Natural, self.oct)
+        } else if accidental.is_flat() {
+            Pitch::new(
+                Letter::from_idx(letter.idx() + 1),
+                Accidental::Flat,
+                self.oct,
+            )
+        } else {
+            Pitch::new(letter, Accidental::Sharp, self.oct)
+        }
+    }
+
+    /// Transposes the key by the number of semitones, which may be negative.
+    pub fn transpose(&self, semitones: i16) -> Result<Self, Error> {
+        let midi = self.to_midi().as_int() as i16 + semitones;
+
+        u8::try_from(midi)
+            .ok()
+            .filter(|midi| *midi <= 127)
+            .ok_or(Error::KeyOutOfRange)
+            .and_then(|midi| Self::try_from_midi(midi.into()))
+    }
+
+    /// Transposes the key up by the interval.
+    pub fn transpose_up(&self, interval: Interval) -> Result<Self, Error> {
+        self.transpose(interval.semitones())
+    }
+
+    /// Transposes the key down by the interval.
+    pub fn transpose_down(&self, interval: Interval) -> Result<Self, Error> {
+        self.transpose(-interval.semitones())
+    }
+
+    /// Returns the signed number of semitones from this key to `other`.
+    pub fn semitones_to(&self, other: &Self) -> i8 {
+        other.to_midi().as_int() as i8 - self.to_midi().as_int() as i8
+    }
+
     pub fn try_from_midi(key: u7) -> Result<Self, Error> {
         if is_valid_key(key) {
             let key = key.as_int() - Self::OFFSET;
@@ -147,18 +873,85 @@ impl Key {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Default, Display, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyboardSize {
+    #[display("25 keys")]
+    Keys25,
+
+    #[display("37 keys")]
+    Keys37,
+
+    #[display("49 keys")]
+    Keys49,
+
+    #[display("61 keys")]
+    Keys61,
+
+    #[display("76 keys")]
+    Keys76,
+
+    #[default]
+    #[display("88 keys")]
+    Keys88,
+
+    #[display("Custom")]
+    Custom(Keyboard),
+}
+
+impl KeyboardSize {
+    pub const PRESETS: [Self; 6] = [
+        Self::Keys25,
+        Self::Keys37,
+        Self::Keys49,
+        Self::Keys61,
+        Self::Keys76,
+        Self::Keys88,
+    ];
+
+    pub fn is_custom(&self) -> bool {
+        matches!(self, Self::Custom(_))
+    }
+
+    pub fn to_keyboard(&self) -> Keyboard {
+        let range = match self {
+            Self::Keys25 => 48..=72,
+            Self::Keys37 => 48..=84,
+            Self::Keys49 => 36..=84,
+            Self::Keys61 => 36..=96,
+            Self::Keys76 => 28..=103,
+            Self::Keys88 => KEY_RANGE_88,
+            Self::Custom(kbd) => return kbd.clone(),
+        };
+
+        Keyboard { range }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Keyboard {
     range: RangeInclusive<u8>,
 }
 
 impl Keyboard {
-    fn new(range: RangeInclusive<u8>) -> Self {
-        Self { range }
+    /// Creates a keyboard with the specified range of key codes. The range must
+    /// be within the range of a standard 88-key piano, and start and end with
+    /// natural keys.
+    pub fn new(range: RangeInclusive<u8>) -> Result<Self, Error> {
+        let is_natural = |key: u8| Key::try_from_midi(key.into()).is_ok_and(|key| key.is_natural());
+
+        if range.start() <= range.end() && is_natural(*range.start()) && is_natural(*range.end()) {
+            Ok(Self { range })
+        } else {
+            Err(Error::InvalidKeyboardRange)
+        }
     }
 
     pub fn standard_88_key() -> Self {
-        Self::new(KEY_RANGE_88)
+        KeyboardSize::Keys88.to_keyboard()
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        self.range.contains(&key.to_midi().as_int())
     }
 
     pub fn first(&self) -> Key {
@@ -194,6 +987,151 @@ impl Keyboard {
     }
 }
 
+impl fmt::Display for Keyboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.first(), self.last())
+    }
+}
+
+/// Parses keyboard ranges in scientific pitch notation, e.g. `A0-C8`.
+impl FromStr for Keyboard {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<KeyRange>()?.try_into()
+    }
+}
+
+impl TryFrom<KeyRange> for Keyboard {
+    type Error = Error;
+
+    fn try_from(range: KeyRange) -> Result<Self, Self::Error> {
+        Self::new(range.first.to_midi().as_int()..=range.last.to_midi().as_int())
+    }
+}
+
+/// Serialized in scientific pitch notation, e.g. `A0-C8`.
+impl Serialize for Keyboard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Also accepts the key code range the keyboard used to be stored as, e.g.
+/// `{"start":21,"end":108}`.
+impl<'de> Deserialize<'de> for Keyboard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Notation(String),
+            Codes(RangeInclusive<u8>),
+        }
+
+        match Stored::deserialize(deserializer)? {
+            Stored::Notation(s) => s.parse(),
+            Stored::Codes(range) => Self::new(range),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+/// Range of keys, e.g. `C#4-F5`. Unlike a [`Keyboard`], the range may start and
+/// end with any key.
+#[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[display("{first}-{last}")]
+pub struct KeyRange {
+    first: Key,
+    last: Key,
+}
+
+impl KeyRange {
+    pub fn new(first: Key, last: Key) -> Result<Self, Error> {
+        if first.to_midi() <= last.to_midi() {
+            Ok(Self { first, last })
+        } else {
+            Err(Error::InvalidKeyRange)
+        }
+    }
+
+    pub fn first(&self) -> Key {
+        self.first
+    }
+
+    pub fn last(&self) -> Key {
+        self.last
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        (self.first.to_midi()..=self.last.to_midi()).contains(&key.to_midi())
+    }
+
+    pub fn iter_keys(&self) -> impl Iterator<Item = Key> + use<> {
+        range(&self.first, &self.last)
+    }
+}
+
+/// Parses key ranges in scientific pitch notation, e.g. `C#4-F5`.
+impl FromStr for KeyRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, last) = s
+            .split_once('-')
+            .ok_or_else(|| Error::InvalidPitch(s.to_owned()))?;
+
+        Self::new(first.parse()?, last.parse()?)
+    }
+}
+
+impl From<&Keyboard> for KeyRange {
+    fn from(kbd: &Keyboard) -> Self {
+        Self {
+            first: kbd.first(),
+            last: kbd.last(),
+        }
+    }
+}
+
+/// Serialized in scientific pitch notation, e.g. `C#4-F5`.
+impl Serialize for KeyRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Parses keys in scientific pitch notation, e.g. `C4`, `C#4` or `Bb3`.
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Pitch>()?.to_key()
+    }
+}
+
+/// Serialized in scientific pitch notation, e.g. `C#4`.
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 impl PartialOrd<Key> for Key {
     fn partial_cmp(&self, other: &Key) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -257,11 +1195,395 @@ mod test {
         }
     }
 
+    #[wasm_bindgen_test]
+    fn pitch_spelling() {
+        let key = KeyPos::ASharp.oct(3);
+        assert_eq!(
+            key.spell(Accidental::Sharp),
+            Pitch::new(Letter::A, Accidental::Sharp, 3)
+        );
+        assert_eq!(
+            key.spell(Accidental::Flat),
+            Pitch::new(Letter::B, Accidental::Flat, 3)
+        );
+        assert_eq!(
+            KeyPos::E.oct(4).spell(Accidental::Flat),
+            Pitch::new(Letter::E, Accidental::Natural, 4)
+        );
+
+        for key in Keyboard::standard_88_key().iter_keys() {
+            assert_eq!(key.spell(Accidental::Sharp).to_key().unwrap(), key);
+            assert_eq!(key.spell(Accidental::Flat).to_key().unwrap(), key);
+        }
+
+        let enharmonics = [
+            (Pitch::new(Letter::C, Accidental::Flat, 4), KeyPos::B.oct(3)),
+            (
+                Pitch::new(Letter::B, Accidental::Sharp, 3),
+                KeyPos::C.oct(4),
+            ),
+            (
+                Pitch::new(Letter::F, Accidental::DoubleSharp, 4),
+                KeyPos::G.oct(4),
+            ),
+            (
+                Pitch::new(Letter::E, Accidental::DoubleFlat, 4),
+                KeyPos::D.oct(4),
+            ),
+            (
+                Pitch::new(Letter::E, Accidental::Sharp, 2),
+                KeyPos::F.oct(2),
+            ),
+        ];
+
+        for (pitch, key) in enharmonics {
+            assert_eq!(pitch.to_key().unwrap(), key);
+        }
+
+        assert!(Pitch::new(Letter::A, Accidental::Flat, 0).to_key().is_err());
+        assert!(
+            Pitch::new(Letter::C, Accidental::Sharp, 8)
+                .to_key()
+                .is_err()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn key_signatures() {
+        let tonics = KeySignature::ALL.map(|sig| sig.to_string());
+        assert_eq!(tonics, [
+            "C major", "G major", "D major", "A major", "E major", "B major", "F# major",
+            "C# major", "F major", "Bb major", "Eb major", "Ab major", "Db major", "Gb major",
+            "Cb major",
+        ]);
+
+        let d_major = KeySignature::try_from(2).unwrap();
+        assert_eq!(d_major.accidental(Letter::F), Accidental::Sharp);
+        assert_eq!(d_major.accidental(Letter::C), Accidental::Sharp);
+        assert_eq!(d_major.accidental(Letter::G), Accidental::Natural);
+        assert!(d_major.is_diatonic(KeyPos::FSharp.oct(4)));
+        assert!(!d_major.is_diatonic(KeyPos::F.oct(4)));
+        assert_eq!(
+            d_major.spell_chromatic(KeyPos::F.oct(4)),
+            Pitch::new(Letter::F, Accidental::Natural, 4)
+        );
+
+        let e_flat_major = KeySignature::try_from(-3).unwrap();
+        assert_eq!(
+            e_flat_major.spell(KeyPos::GSharp.oct(3)),
+            Some(Pitch::new(Letter::A, Accidental::Flat, 3))
+        );
+        assert_eq!(
+            e_flat_major.spell_chromatic(KeyPos::FSharp.oct(3)),
+            Pitch::new(Letter::G, Accidental::Flat, 3)
+        );
+
+        let c_flat_major = KeySignature::try_from(-7).unwrap();
+        assert_eq!(
+            c_flat_major.spell(KeyPos::B.oct(3)),
+            Some(Pitch::new(Letter::C, Accidental::Flat, 4))
+        );
+
+        let c_sharp_major = KeySignature::try_from(7).unwrap();
+        assert_eq!(
+            c_sharp_major.spell(KeyPos::C.oct(4)),
+            Some(Pitch::new(Letter::B, Accidental::Sharp, 3))
+        );
+
+        for sig in KeySignature::ALL {
+            let diatonic = Keyboard::standard_88_key()
+                .iter_keys()
+                .filter(|key| key.oct == 4)
+                .filter(|key| sig.is_diatonic(*key))
+                .count();
+            assert_eq!(diatonic, 7);
+        }
+
+        assert!(KeySignature::try_from(8).is_err());
+        assert!(KeySignature::try_from(-8).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn intervals() {
+        let semitones = [
+            (Interval::UNISON, 0),
+            (Interval::MINOR_SECOND, 1),
+            (Interval::MAJOR_THIRD, 4),
+            (Interval::AUGMENTED_FOURTH, 6),
+            (Interval::DIMINISHED_FIFTH, 6),
+            (Interval::MINOR_SIXTH, 8),
+            (Interval::DIMINISHED_SEVENTH, 9),
+            (Interval::MAJOR_SEVENTH, 11),
+            (Interval::OCTAVE, 12),
+            (Interval::new(Quality::Major, 10).unwrap(), 16),
+            (Interval::new(Quality::Perfect, 12).unwrap(), 19),
+        ];
+
+        for (interval, num) in semitones {
+            assert_eq!(interval.semitones(), num, "{interval}");
+        }
+
+        assert!(Interval::new(Quality::Major, 5).is_err());
+        assert!(Interval::new(Quality::Perfect, 3).is_err());
+        assert!(Interval::new(Quality::Diminished, 1).is_err());
+        assert!(Interval::new(Quality::Minor, 0).is_err());
+        assert!(Interval::new(Quality::Augmented, 23).is_err());
+        assert!(Interval::new(Quality::Augmented, 255).is_err());
+        assert_eq!(
+            Interval::new(Quality::Perfect, Interval::MAX_NUMBER)
+                .unwrap()
+                .semitones(),
+            36
+        );
+        assert_eq!(Interval::MINOR_SEVENTH.to_string(), "m7");
+
+        let c4 = Pitch::new(Letter::C, Accidental::Natural, 4);
+        let transposed = [
+            (
+                c4,
+                Interval::MINOR_THIRD,
+                Pitch::new(Letter::E, Accidental::Flat, 4),
+            ),
+            (
+                c4,
+                Interval::AUGMENTED_FOURTH,
+                Pitch::new(Letter::F, Accidental::Sharp, 4),
+            ),
+            (
+                c4,
+                Interval::DIMINISHED_FIFTH,
+                Pitch::new(Letter::G, Accidental::Flat, 4),
+            ),
+            (
+                c4,
+                Interval::OCTAVE,
+                Pitch::new(Letter::C, Accidental::Natural, 5),
+            ),
+            (
+                Pitch::new(Letter::B, Accidental::Natural, 3),
+                Interval::MINOR_SECOND,
+                Pitch::new(Letter::C, Accidental::Natural, 4),
+            ),
+            (
+                Pitch::new(Letter::F, Accidental::Sharp, 4),
+                Interval::AUGMENTED_FOURTH,
+                Pitch::new(Letter::B, Accidental::Sharp, 4),
+            ),
+            (
+                Pitch::new(Letter::A, Accidental::Flat, 2),
+                Interval::new(Quality::Major, 10).unwrap(),
+                Pitch::new(Letter::C, Accidental::Natural, 4),
+            ),
+        ];
+
+        for (pitch, interval, expected) in transposed {
+            let result = pitch.transpose(interval).unwrap();
+            assert_eq!(result, expected);
+            assert_eq!(Interval::between(&pitch, &result), Some(interval));
+        }
+
+        assert!(
+            Pitch::new(Letter::B, Accidental::DoubleSharp, 4)
+                .transpose(Interval::AUGMENTED_FOURTH)
+                .is_err()
+        );
+        assert_eq!(
+            Interval::between(&Pitch::new(Letter::D, Accidental::Natural, 4), &c4),
+            None
+        );
+
+        let key = KeyPos::C.oct(4);
+        assert_eq!(
+            key.transpose_up(Interval::PERFECT_FIFTH).unwrap(),
+            KeyPos::G.oct(4)
+        );
+        assert_eq!(
+            key.transpose_down(Interval::MINOR_THIRD).unwrap(),
+            KeyPos::A.oct(3)
+        );
+        assert_eq!(key.transpose(-12).unwrap(), KeyPos::C.oct(3));
+        assert_eq!(key.semitones_to(&KeyPos::A.oct(3)), -3);
+        assert_eq!(key.semitones_to(&KeyPos::E.oct(5)), 16);
+        assert!(KeyPos::A.oct(0).transpose(-1).is_err());
+        assert!(KeyPos::C.oct(8).transpose(1).is_err());
+        assert!(
+            KeyPos::A
+                .oct(0)
+                .transpose_up(Interval::new(Quality::Augmented, Interval::MAX_NUMBER).unwrap())
+                .is_ok()
+        );
+        assert_eq!(
+            Interval::between(
+                &Pitch::new(Letter::C, Accidental::Natural, 0),
+                &Pitch::new(Letter::C, Accidental::Natural, 8)
+            ),
+            None
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn chords() {
+        let pitch = |letter, accidental, oct| Pitch::new(letter, accidental, oct);
+        let nat = Accidental::Natural;
+
+        let c_major = Chord::new(pitch(Letter::C, nat, 4), ChordKind::Major);
+        assert_eq!(c_major.pitches().unwrap(), [
+            pitch(Letter::C, nat, 4),
+            pitch(Letter::E, nat, 4),
+            pitch(Letter::G, nat, 4),
+        ]);
+        assert_eq!(c_major.inversion(1).pitches().unwrap(), [
+            pitch(Letter::E, nat, 4),
+            pitch(Letter::G, nat, 4),
+            pitch(Letter::C, nat, 5),
+        ]);
+        assert_eq!(c_major.inversion(2).keys().unwrap(), [
+            KeyPos::G.oct(4),
+            KeyPos::C.oct(5),
+            KeyPos::E.oct(5),
+        ]);
+        assert!(c_major.inversion(3).pitches().is_err());
+
+        let b_diminished = Chord::new(pitch(Letter::B, nat, 3), ChordKind::Diminished);
+        assert_eq!(b_diminished.pitches().unwrap(), [
+            pitch(Letter::B, nat, 3),
+            pitch(Letter::D, nat, 4),
+            pitch(Letter::F, nat, 4),
+        ]);
+
+        let f_sharp_7 = Chord::new(
+            pitch(Letter::F, Accidental::Sharp, 3),
+            ChordKind::DominantSeventh,
+        );
+        assert_eq!(f_sharp_7.pitches().unwrap(), [
+            pitch(Letter::F, Accidental::Sharp, 3),
+            pitch(Letter::A, Accidental::Sharp, 3),
+            pitch(Letter::C, Accidental::Sharp, 4),
+            pitch(Letter::E, nat, 4),
+        ]);
+
+        let g_half_diminished =
+            Chord::new(pitch(Letter::G, nat, 4), ChordKind::HalfDiminishedSeventh);
+        assert_eq!(g_half_diminished.pitches().unwrap(), [
+            pitch(Letter::G, nat, 4),
+            pitch(Letter::B, Accidental::Flat, 4),
+            pitch(Letter::D, Accidental::Flat, 5),
+            pitch(Letter::F, nat, 5),
+        ]);
+
+        let c_diminished_7 = Chord::new(pitch(Letter::C, nat, 4), ChordKind::DiminishedSeventh);
+        assert_eq!(c_diminished_7.inversion(3).pitches().unwrap(), [
+            pitch(Letter::B, Accidental::DoubleFlat, 4),
+            pitch(Letter::C, nat, 5),
+            pitch(Letter::E, Accidental::Flat, 5),
+            pitch(Letter::G, Accidental::Flat, 5),
+        ]);
+
+        for kind in ChordKind::TRIADS.into_iter().chain(ChordKind::SEVENTHS) {
+            let keys = Chord::new(pitch(Letter::D, nat, 4), kind).keys().unwrap();
+            assert_eq!(keys.len(), kind.num_notes());
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn parsing() {
+        let keys = [
+            ("A0", 21),
+            ("C4", 60),
+            ("c4", 60),
+            ("C#4", 61),
+            ("Db4", 61),
+            ("Bb3", 58),
+            ("B\u{266d}3", 58),
+            ("Cb4", 59),
+            ("B#3", 60),
+            ("Fx4", 67),
+            ("Ebb4", 62),
+            ("C8", 108),
+        ];
+
+        for (s, midi) in keys {
+            assert_eq!(s.parse::<Key>().unwrap().to_midi(), midi, "{s}");
+        }
+
+        for s in ["", "H4", "C", "C#", "C$4", "Cb0", "C#8", "C10", "C-1"] {
+            assert!(s.parse::<Key>().is_err(), "{s}");
+        }
+
+        assert_eq!(
+            "Bb3".parse::<Pitch>().unwrap(),
+            Pitch::new(Letter::B, Accidental::Flat, 3)
+        );
+
+        for key in Keyboard::standard_88_key().iter_keys() {
+            let serialized = serde_json::to_string(&key).unwrap();
+            let deserialized = serde_json::from_str::<Key>(&serialized).unwrap();
+            assert_eq!(deserialized.to_midi(), key.to_midi());
+
+            let spelled = key.spell(Accidental::Flat).to_string();
+            assert_eq!(spelled.parse::<Key>().unwrap(), key);
+        }
+
+        assert_eq!(
+            serde_json::to_string(&KeyPos::CSharp.oct(4)).unwrap(),
+            "\"C#4\""
+        );
+        assert!(serde_json::from_str::<Key>("\"X4\"").is_err());
+
+        let kbd = "A0-C8".parse::<Keyboard>().unwrap();
+        assert_eq!(kbd, Keyboard::standard_88_key());
+        assert_eq!(serde_json::to_string(&kbd).unwrap(), "\"A0-C8\"");
+
+        let kbd = serde_json::from_str::<Keyboard>("\"C2-C7\"").unwrap();
+        assert_eq!(kbd, KeyboardSize::Keys61.to_keyboard());
+        assert!("C4-C#5".parse::<Keyboard>().is_err());
+        assert!("C4".parse::<Keyboard>().is_err());
+
+        // Keyboards stored before the notation was introduced.
+        let kbd = serde_json::from_str::<Keyboard>(r#"{"start":36,"end":96}"#).unwrap();
+        assert_eq!(kbd, KeyboardSize::Keys61.to_keyboard());
+        assert!(serde_json::from_str::<Keyboard>(r#"{"start":37,"end":96}"#).is_err());
+
+        let range = "C#4-F5".parse::<KeyRange>().unwrap();
+        assert_eq!(range.first(), KeyPos::CSharp.oct(4));
+        assert_eq!(range.last(), KeyPos::F.oct(5));
+        assert_eq!(range.iter_keys().count(), 17);
+        assert!(range.contains(&KeyPos::E.oct(5)));
+        assert!(!range.contains(&KeyPos::C.oct(4)));
+        assert_eq!(serde_json::to_string(&range).unwrap(), "\"C#4-F5\"");
+        assert_eq!(
+            serde_json::from_str::<KeyRange>("\"Db4-F5\"").unwrap(),
+            range
+        );
+        assert!("F5-C#4".parse::<KeyRange>().is_err());
+        assert_eq!(
+            KeyRange::from(&Keyboard::standard_88_key()).to_string(),
+            "A0-C8"
+        );
+    }
+
     #[wasm_bindgen_test]
     fn keyboard() {
         let kbd = Keyboard::standard_88_key();
         assert_eq!(kbd.num_keys(), 88);
         assert_eq!(kbd.num_sharp_keys(), 36);
         assert_eq!(kbd.num_natural_keys(), 52);
+
+        for (size, num_keys) in KeyboardSize::PRESETS.iter().zip([25, 37, 49, 61, 76, 88]) {
+            let kbd = size.to_keyboard();
+            assert_eq!(kbd.num_keys(), num_keys);
+            assert!(kbd.first().is_natural());
+            assert!(kbd.last().is_natural());
+        }
+
+        let kbd = Keyboard::new(60..=72).unwrap();
+        assert!(kbd.contains(&KeyPos::C.oct(4)));
+        assert!(kbd.contains(&KeyPos::C.oct(5)));
+        assert!(!kbd.contains(&KeyPos::B.oct(3)));
+
+        assert!(Keyboard::new(61..=72).is_err());
+        assert!(Keyboard::new(RangeInclusive::new(72, 60)).is_err());
+        assert!(Keyboard::new(12..=60).is_err());
     }
 }
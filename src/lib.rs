@@ -3,9 +3,13 @@
 use {app::App, tap::TapFallible, wasm_bindgen::prelude::*};
 
 pub mod app;
+pub mod audio;
 pub mod input;
 pub mod keyboard;
+pub mod naming;
 pub mod piano;
+pub mod scale;
+pub mod synth;
 pub mod util;
 pub mod verovio;
 
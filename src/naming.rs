@@ -0,0 +1,233 @@
+use {
+    crate::keyboard::{Accidental, Key, KeySignature, Letter, Pitch, PitchClass},
+    derive_more::Display,
+    serde::{Deserialize, Serialize},
+    std::fmt,
+};
+
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteNames {
+    #[default]
+    #[display("English (C, D, E)")]
+    English,
+
+    #[display("German (C, D, E, H)")]
+    German,
+
+    #[display("Solfège (Do, Re, Mi)")]
+    Solfege,
+}
+
+impl NoteNames {
+    pub const ALL: [Self; 3] = [Self::English, Self::German, Self::Solfege];
+}
+
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OctaveNotation {
+    #[default]
+    #[display("Scientific (C4)")]
+    Scientific,
+
+    #[display("Helmholtz (c')")]
+    Helmholtz,
+}
+
+impl OctaveNotation {
+    pub const ALL: [Self; 2] = [Self::Scientific, Self::Helmholtz];
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Naming {
+    pub names: NoteNames,
+    pub octaves: OctaveNotation,
+}
+
+impl Naming {
+    pub const fn new(names: NoteNames, octaves: OctaveNotation) -> Self {
+        Self { names, octaves }
+    }
+
+    /// Returns the name of the pitch class, e.g. `Bb` (English), `B` (German)
+    /// or `Sib` (solfège).
+    pub fn pitch_class(&self, letter: Letter, accidental: Accidental) -> String {
+        match self.names {
+            NoteNames::English => format!("{}{}", letter.as_str(), accidental.as_str()),
+            NoteNames::German => german_name(letter, accidental),
+            NoteNames::Solfege => format!("{}{}", solfege_name(letter), accidental.as_str()),
+        }
+    }
+
+    /// Returns the full name of the pitch including octave, e.g. `C#4` or
+    /// `cis'`.
+    pub fn pitch(&self, pitch: &Pitch) -> String {
+        let name = match (self.names, self.octaves) {
+            // Lowercase letters make the `b` flat ambiguous (`bb` would read as
+            // B double flat), so the accidental symbols are used instead.
+            (NoteNames::English, OctaveNotation::Helmholtz) => {
+                format!(
+                    "{}{}",
+                    pitch.letter.as_str(),
+                    accidental_symbol(pitch.accidental)
+                )
+            }
+            _ => self.pitch_class(pitch.letter, pitch.accidental),
+        };
+
+        match self.octaves {
+            OctaveNotation::Scientific => format!("{name}{}", pitch.oct),
+
+            // Octaves starting with the middle C are marked with primes, octaves
+            // below the great octave are marked with commas.
+            OctaveNotation::Helmholtz if pitch.oct >= 3 => {
+                let marks = "'".repeat(pitch.oct as usize - 3);
+                format!("{}{marks}", name.to_lowercase())
+            }
+
+            OctaveNotation::Helmholtz => {
+                let marks = ",".repeat(2 - pitch.oct as usize);
+                format!("{name}{marks}")
+            }
+        }
+    }
+
+    /// Wraps the value, so it's displayed with this naming system, e.g. in
+    /// pick lists.
+    pub fn named<T>(&self, value: T) -> Named<T> {
+        Named {
+            value,
+            naming: *self,
+        }
+    }
+}
+
+/// Value displayed with a naming system. The [`fmt::Display`] implementations
+/// of the values themselves always use English names in scientific notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Named<T> {
+    pub value: T,
+    pub naming: Naming,
+}
+
+impl fmt::Display for Named<PitchClass> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            &self
+                .naming
+                .pitch_class(self.value.letter, self.value.accidental),
+        )
+    }
+}
+
+impl fmt::Display for Named<Key> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.naming.pitch(&self.value.into()))
+    }
+}
+
+impl fmt::Display for Named<KeySignature> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} major", self.naming.named(self.value.tonic()))
+    }
+}
+
+fn accidental_symbol(accidental: Accidental) -> &'static str {
+    match accidental {
+        Accidental::DoubleFlat => "♭♭",
+        Accidental::Flat => "♭",
+        Accidental::Natural => "",
+        Accidental::Sharp => "♯",
+        Accidental::DoubleSharp => "♯♯",
+    }
+}
+
+fn german_name(letter: Letter, accidental: Accidental) -> String {
+    match (letter, accidental) {
+        (Letter::B, Accidental::Natural) => "H".to_owned(),
+        (Letter::B, Accidental::Flat) => "B".to_owned(),
+        (Letter::B, Accidental::DoubleFlat) => "Heses".to_owned(),
+        (Letter::E, Accidental::Flat) => "Es".to_owned(),
+        (Letter::E, Accidental::DoubleFlat) => "Eses".to_owned(),
+        (Letter::A, Accidental::Flat) => "As".to_owned(),
+        (Letter::A, Accidental::DoubleFlat) => "Asas".to_owned(),
+        (letter, accidental) => {
+            let letter = if letter == Letter::B {
+                "H"
+            } else {
+                letter.as_str()
+            };
+
+            let suffix = match accidental {
+                Accidental::DoubleFlat => "eses",
+                Accidental::Flat => "es",
+                Accidental::Natural => "",
+                Accidental::Sharp => "is",
+                Accidental::DoubleSharp => "isis",
+            };
+
+            format!("{letter}{suffix}")
+        }
+    }
+}
+
+fn solfege_name(letter: Letter) -> &'static str {
+    match letter {
+        Letter::C => "Do",
+        Letter::D => "Re",
+        Letter::E => "Mi",
+        Letter::F => "Fa",
+        Letter::G => "Sol",
+        Letter::A => "La",
+        Letter::B => "Si",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, wasm_bindgen_test::*};
+
+    #[wasm_bindgen_test]
+    fn pitch_names() {
+        let pitches = [
+            Pitch::new(Letter::C, Accidental::Natural, 4),
+            Pitch::new(Letter::B, Accidental::Flat, 3),
+            Pitch::new(Letter::F, Accidental::Sharp, 5),
+            Pitch::new(Letter::E, Accidental::Flat, 2),
+            Pitch::new(Letter::B, Accidental::Natural, 0),
+        ];
+
+        let names = |naming: Naming| pitches.map(|pitch| naming.pitch(&pitch));
+
+        assert_eq!(
+            names(Naming::new(NoteNames::English, OctaveNotation::Scientific)),
+            ["C4", "Bb3", "F#5", "Eb2", "B0"]
+        );
+        assert_eq!(
+            names(Naming::new(NoteNames::German, OctaveNotation::Scientific)),
+            ["C4", "B3", "Fis5", "Es2", "H0"]
+        );
+        assert_eq!(
+            names(Naming::new(NoteNames::Solfege, OctaveNotation::Scientific)),
+            ["Do4", "Sib3", "Fa#5", "Mib2", "Si0"]
+        );
+        assert_eq!(
+            names(Naming::new(NoteNames::English, OctaveNotation::Helmholtz)),
+            ["c'", "b♭", "f♯''", "E♭", "B,,"]
+        );
+        assert_eq!(
+            names(Naming::new(NoteNames::German, OctaveNotation::Helmholtz)),
+            ["c'", "b", "fis''", "Es", "H,,"]
+        );
+
+        // Display is canonical, the naming system only applies when requested.
+        assert_eq!(pitches.map(|pitch| pitch.to_string()), [
+            "C4", "Bb3", "F#5", "Eb2", "B0"
+        ]);
+
+        let german = Naming::new(NoteNames::German, OctaveNotation::Scientific);
+        let key = KeySignature::ALL
+            .into_iter()
+            .find(|key| key.tonic() == PitchClass::new(Letter::B, Accidental::Flat))
+            .unwrap();
+        assert_eq!(german.named(key).to_string(), "B major");
+    }
+}
@@ -1,63 +1,288 @@
 use {
     crate::{
         app::Message,
-        keyboard::{Key, Keyboard},
+        input::{self, Timestamp, pedal::Pedal},
+        keyboard::{Key, KeyPos, Keyboard, Pitch},
+        naming::Naming,
     },
+    derive_more::Display,
     iced::{
         Color,
         Element,
+        Pixels,
         Point,
         Rectangle,
         Renderer,
         Size,
         Theme,
         Vector,
+        alignment,
+        keyboard,
         mouse,
+        touch,
         widget::{
             Canvas,
-            canvas::{self, Fill, Frame, Stroke},
+            canvas::{self, Fill, Frame, Path, Stroke, Text},
         },
     },
-    std::{cmp::Ordering, collections::HashSet},
+    midly::{MidiMessage, num::u7},
+    serde::{Deserialize, Serialize},
+    std::{cmp::Ordering, collections::HashMap},
 };
 
 const SHARP_KEY_HEIGHT: f32 = 0.6;
+const MAX_LABEL_SIZE: f32 = 14.;
+const MAX_NATURAL_WIDTH: f32 = 40.;
+const MIN_VISIBLE_KEYS: f32 = 7.;
+const ZOOM_STEP: f32 = 0.1;
+/// Number of natural keys shown around the focused range.
+const FOCUS_PADDING: usize = 2;
+const MIN_VELOCITY: u8 = 16;
+const MAX_VELOCITY: u8 = 127;
+const MIDDLE_C: Key = KeyPos::C.oct(4);
+const MARKER_COLOR: Color = Color::from_rgb(0.2, 0.5, 0.9);
+const PEDAL_LABEL_SIZE: f32 = 12.;
+const PEDAL_LABEL_WIDTH: f32 = 80.;
+const CONTROL_SIZE: f32 = 36.;
+const CONTROL_PADDING: f32 = 6.;
+
+/// Landmark notes commonly used for reading the grand staff: low C, bass F,
+/// middle C, treble G and high C.
+const LANDMARKS: [Key; 5] = [
+    KeyPos::C.oct(2),
+    KeyPos::F.oct(3),
+    MIDDLE_C,
+    KeyPos::G.oct(4),
+    KeyPos::C.oct(6),
+];
+
+/// Note names displayed on the keys.
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyLabels {
+    #[default]
+    #[display("No labels")]
+    None,
+
+    #[display("Label C keys")]
+    C,
+
+    #[display("Label landmark notes")]
+    Landmarks,
+
+    #[display("Label all keys")]
+    All,
+}
+
+impl KeyLabels {
+    pub const ALL: [Self; 4] = [Self::None, Self::C, Self::Landmarks, Self::All];
+
+    /// Returns the label for the key, if it should be labeled.
+    fn label(&self, key: Key, naming: Naming) -> Option<String> {
+        match self {
+            Self::None => None,
+            Self::C => (key.pos == KeyPos::C).then(|| naming.pitch(&key.into())),
+            Self::Landmarks => LANDMARKS.contains(&key).then(|| naming.pitch(&key.into())),
+
+            // Only C keys include the octave to keep the labels short.
+            Self::All if key.pos == KeyPos::C => Some(naming.pitch(&key.into())),
+            Self::All => {
+                let pitch = Pitch::from(key);
+                Some(naming.pitch_class(pitch.letter, pitch.accidental))
+            }
+        }
+    }
+}
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyState {
     Released,
+    /// Pressed while there's no challenge to validate it against.
     Pressed,
+    /// Pressed and expected by the challenge.
+    Correct,
+    /// Pressed, but not expected by the challenge.
+    Incorrect,
+}
+
+impl KeyState {
+    fn color(&self) -> Option<Color> {
+        match self {
+            Self::Released => None,
+            Self::Pressed => Some(Color::from_rgb(0.7, 0.7, 0.7)),
+            Self::Correct => Some(Color::from_rgb(0.45, 0.8, 0.45)),
+            Self::Incorrect => Some(Color::from_rgb(0.9, 0.4, 0.4)),
+        }
+    }
+}
+
+/// Highlights released keys to reveal the challenge solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// The key expected by the challenge.
+    Expected,
+    /// A key with the same pitch class as the expected key, without revealing
+    /// the octave.
+    Hinted,
+}
+
+impl Highlight {
+    fn color(&self, is_natural: bool) -> Color {
+        match (self, is_natural) {
+            (Self::Expected, true) => Color::from_rgb(0.4, 0.6, 0.95),
+            (Self::Expected, false) => Color::from_rgb(0.2, 0.35, 0.75),
+            (Self::Hinted, true) => Color::from_rgb(0.8, 0.87, 1.),
+            (Self::Hinted, false) => Color::from_rgb(0.3, 0.35, 0.5),
+        }
+    }
+}
+
+/// Visible part of the keyboard, measured in natural keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct View {
+    start: f32,
+    len: f32,
+}
+
+impl View {
+    fn clamp(self, num_natural_keys: f32) -> Self {
+        let len = self
+            .len
+            .clamp(MIN_VISIBLE_KEYS.min(num_natural_keys), num_natural_keys);
+
+        Self {
+            start: self.start.clamp(0., num_natural_keys - len),
+            len,
+        }
+    }
+}
+
+/// Zoom and scroll buttons shown on touch screens, which have no scroll wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    ZoomOut,
+    ZoomIn,
+    ScrollLeft,
+    ScrollRight,
+}
+
+impl Control {
+    const ALL: [Self; 4] = [
+        Self::ZoomOut,
+        Self::ZoomIn,
+        Self::ScrollLeft,
+        Self::ScrollRight,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::ZoomOut => "−",
+            Self::ZoomIn => "+",
+            Self::ScrollLeft => "‹",
+            Self::ScrollRight => "›",
+        }
+    }
+
+    /// Returns the top left corner of the button in the top left corner of the
+    /// canvas.
+    fn position(&self) -> Point {
+        Point::new(
+            CONTROL_PADDING + *self as usize as f32 * (CONTROL_SIZE + CONTROL_PADDING),
+            CONTROL_PADDING,
+        )
+    }
+
+    fn contains(&self, pt: Point) -> bool {
+        Rectangle::new(self.position(), Size::new(CONTROL_SIZE, CONTROL_SIZE)).contains(pt)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Pointer {
+    Mouse,
+    Finger(touch::Finger),
 }
 
 #[derive(Default)]
 pub struct State {
-    pressed_key: Option<Key>,
+    /// Keys held by the mouse and each finger on touch screens.
+    held: HashMap<Pointer, Key>,
+    mouse_down: bool,
+    modifiers: keyboard::Modifiers,
     bounds: Rectangle,
+    view: Option<View>,
+    key_width: f32,
     translation: Vector,
     scale: Vector,
+    /// Whether touch input was used, which shows the zoom and scroll controls.
+    touch: bool,
 }
 
 impl State {
-    fn update_translation(&mut self, kbd: &Keyboard) {
-        let kbd_height = 150.;
-        let kbd_width = 23.5 * kbd.num_natural_keys() as f32;
-        let desired_aspect_ratio = kbd_width / kbd_height;
+    fn update_translation(&mut self, piano: &Piano) {
+        let num_natural_keys = piano.natural_keys.len() as f32;
         // Shrink the actual usable bounds a little bit to give space for the strokes
         // outside of shape bounds.
         let bounds = self.bounds.shrink(iced::Padding::new(2.));
-        let widget_aspect_ratio = bounds.width / bounds.height;
+        let view = self
+            .view
+            .get_or_insert_with(|| piano.default_view(bounds.width))
+            .clamp(num_natural_keys);
+        self.view = Some(view);
 
-        let (width, height) = if widget_aspect_ratio > desired_aspect_ratio {
-            (bounds.height * desired_aspect_ratio, bounds.height)
-        } else {
-            (bounds.width, bounds.width / desired_aspect_ratio)
-        };
+        let key_width = (bounds.width / view.len).min(MAX_NATURAL_WIDTH);
+        // Center the keyboard if it doesn't fill the available space.
+        let margin = (bounds.width - key_width * view.len) / 2.;
 
         self.translation = Vector::new(
-            (self.bounds.width - width) / 2.,
-            (self.bounds.height - height) / 2.,
+            bounds.x - self.bounds.x + margin - view.start * key_width,
+            bounds.y - self.bounds.y,
         );
-        self.scale = Vector::new(width, height);
+        self.scale = Vector::new(key_width * num_natural_keys, bounds.height);
+        self.key_width = key_width;
+    }
+
+    /// Scrolls the view by the number of natural keys.
+    fn pan(&mut self, piano: &Piano, keys: f32) {
+        if let Some(view) = &mut self.view {
+            view.start += keys;
+            self.update_translation(piano);
+        }
+    }
+
+    /// Zooms the view by the factor, keeping the key under the cursor in place.
+    fn zoom(&mut self, piano: &Piano, factor: f32, cursor_x: f32) {
+        if let Some(view) = &mut self.view {
+            let anchor = (cursor_x - self.translation.x) / self.key_width;
+            let len = view.len * factor;
+
+            view.start = anchor - (anchor - view.start) * len / view.len;
+            view.len = len;
+            self.update_translation(piano);
+        }
+    }
+
+    /// Zooms around the center or scrolls by half of the view.
+    fn apply(&mut self, piano: &Piano, control: Control) {
+        let center = self.bounds.width / 2.;
+        let page = self.view.map_or(0., |view| view.len / 2.);
+
+        match control {
+            Control::ZoomOut => self.zoom(piano, 1. + ZOOM_STEP * 2., center),
+            Control::ZoomIn => self.zoom(piano, 1. - ZOOM_STEP * 2., center),
+            Control::ScrollLeft => self.pan(piano, -page),
+            Control::ScrollRight => self.pan(piano, page),
+        }
+    }
+
+    /// Returns the control at the point in window coordinates, if the controls
+    /// are shown.
+    fn find_control(&self, pt: Point) -> Option<Control> {
+        let pt = Point::new(pt.x - self.bounds.x, pt.y - self.bounds.y);
+
+        Control::ALL
+            .into_iter()
+            .filter(|_| self.touch)
+            .find(|control| control.contains(pt))
     }
 
     fn translate(&self, pt: Point) -> Point {
@@ -66,6 +291,19 @@ impl State {
             (pt.y - self.translation.y) / self.scale.y,
         )
     }
+
+    /// Inverse of [`State::translate`].
+    fn untranslate(&self, pt: Point) -> Point {
+        Point::new(
+            pt.x * self.scale.x + self.translation.x,
+            pt.y * self.scale.y + self.translation.y,
+        )
+    }
+
+    /// Returns `true` if the key is held by the mouse or any finger.
+    fn is_held(&self, key: &Key) -> bool {
+        self.held.values().any(|held| held == key)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -78,8 +316,16 @@ struct KeyData {
 pub struct Piano {
     natural_keys: Vec<KeyData>,
     sharp_keys: Vec<KeyData>,
-    pressed_keys: HashSet<Key>,
-    keyboard: Keyboard,
+    key_states: HashMap<Key, KeyState>,
+    highlights: HashMap<Key, Highlight>,
+    /// Range of natural key indices to show initially.
+    focus: Option<(usize, usize)>,
+    labels: KeyLabels,
+    /// Naming system of the key labels.
+    naming: Naming,
+    middle_c_marker: bool,
+    /// Pressed pedals, in the order they were pressed.
+    pedals: Vec<Pedal>,
 }
 
 impl Piano {
@@ -124,8 +370,13 @@ impl Piano {
         Self {
             natural_keys,
             sharp_keys,
-            pressed_keys: Default::default(),
-            keyboard,
+            key_states: Default::default(),
+            highlights: Default::default(),
+            focus: None,
+            labels: KeyLabels::None,
+            naming: Naming::default(),
+            middle_c_marker: false,
+            pedals: Vec::new(),
         }
     }
 
@@ -136,23 +387,244 @@ impl Piano {
             .into()
     }
 
+    /// Sets the range of keys shown initially, with a little padding around it.
+    /// The rest of the keyboard can be reached by scrolling and zooming.
+    pub fn set_focus(&mut self, first: Key, last: Key) {
+        let first = self.natural_keys.partition_point(|data| data.key < first);
+        let last = self.natural_keys.partition_point(|data| data.key <= last);
+
+        if first < last {
+            self.focus = Some((
+                first.saturating_sub(FOCUS_PADDING),
+                (last - 1 + FOCUS_PADDING).min(self.natural_keys.len() - 1),
+            ));
+        }
+    }
+
+    fn default_view(&self, width: f32) -> View {
+        let (first, last) = self
+            .focus
+            .unwrap_or((0, self.natural_keys.len().saturating_sub(1)));
+        let focus_len = (last + 1 - first) as f32;
+        let len = focus_len.max(width / MAX_NATURAL_WIDTH);
+
+        View {
+            start: first as f32 + (focus_len - len) / 2.,
+            len,
+        }
+    }
+
+    pub fn set_labels(&mut self, labels: KeyLabels) {
+        self.labels = labels;
+    }
+
+    pub fn set_naming(&mut self, naming: Naming) {
+        self.naming = naming;
+    }
+
+    pub fn set_middle_c_marker(&mut self, enabled: bool) {
+        self.middle_c_marker = enabled;
+    }
+
+    pub fn set_pedal(&mut self, pedal: Pedal, pressed: bool) {
+        self.pedals.retain(|other| *other != pedal);
+
+        if pressed {
+            self.pedals.push(pedal);
+        }
+    }
+
     pub fn set_key_state(&mut self, key: Key, state: KeyState) {
         match state {
-            KeyState::Pressed => {
-                self.pressed_keys.insert(key);
+            KeyState::Released => {
+                self.key_states.remove(&key);
             }
 
-            KeyState::Released => {
-                self.pressed_keys.remove(&key);
+            state => {
+                self.key_states.insert(key, state);
             }
         }
     }
 
+    pub fn set_highlight(&mut self, key: Key, highlight: Option<Highlight>) {
+        match highlight {
+            Some(highlight) => {
+                self.highlights.insert(key, highlight);
+            }
+
+            None => {
+                self.highlights.remove(&key);
+            }
+        }
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.highlights.clear();
+    }
+
     fn is_pressed(&self, key: &Key) -> bool {
-        self.pressed_keys.contains(key)
+        self.key_states.contains_key(key)
+    }
+
+    /// Returns the fill color of the key, if it differs from the default one.
+    /// Key state takes priority over highlights.
+    fn key_color(&self, key: &Key, is_natural: bool) -> Option<Color> {
+        self.key_states
+            .get(key)
+            .and_then(KeyState::color)
+            .or_else(|| {
+                self.highlights
+                    .get(key)
+                    .map(|highlight| highlight.color(is_natural))
+            })
+    }
+
+    /// Moves the pointer to the target key, releasing the key previously held
+    /// by the pointer. Keys held by other pointers are neither pressed nor
+    /// released.
+    fn move_pointer(
+        &self,
+        state: &mut State,
+        pointer: Pointer,
+        target: Option<(Key, u7)>,
+    ) -> Option<canvas::Action<Message>> {
+        let prev = state.held.get(&pointer).copied();
+
+        if prev == target.map(|(key, _)| key) {
+            return None;
+        }
+
+        let mut messages = Vec::new();
+        let stamp = Timestamp::now();
+
+        if let Some(prev) = prev {
+            state.held.remove(&pointer);
+
+            if !state.is_held(&prev) {
+                messages.push(Message::Input(input::Event::new(
+                    MidiMessage::NoteOff {
+                        key: prev.to_midi(),
+                        vel: 0.into(),
+                    },
+                    stamp,
+                )));
+            }
+        }
+
+        if let Some((key, vel)) = target {
+            if !state.is_held(&key) {
+                messages.push(Message::Input(input::Event::new(
+                    MidiMessage::NoteOn {
+                        key: key.to_midi(),
+                        vel,
+                    },
+                    stamp,
+                )));
+            }
+
+            state.held.insert(pointer, key);
+        }
+
+        let action = match messages.len() {
+            0 => canvas::Action::capture(),
+            1 => canvas::Action::publish(messages.remove(0)).and_capture(),
+            _ => canvas::Action::publish(Message::Batch(messages)).and_capture(),
+        };
+
+        Some(action)
     }
 
-    fn find_key(&self, pt: Point) -> Option<Key> {
+    fn draw_overlays(&self, state: &State, frame: &mut Frame) {
+        let natural_width = state.scale.x / self.natural_keys.len().max(1) as f32;
+        let label_size = (natural_width * 0.5).min(MAX_LABEL_SIZE);
+        let padding = natural_width * 0.15;
+
+        for (key, is_natural) in self
+            .natural_keys
+            .iter()
+            .map(|key| (key, true))
+            .chain(self.sharp_keys.iter().map(|key| (key, false)))
+        {
+            let bottom = state.untranslate(Point::new(
+                key.offset.x + key.size.width / 2.,
+                key.offset.y + key.size.height,
+            ));
+
+            let mut marker_y = bottom.y - padding;
+
+            if let Some(label) = self.labels.label(key.key, self.naming) {
+                let color = if is_natural || self.is_pressed(&key.key) {
+                    Color::BLACK
+                } else {
+                    Color::WHITE
+                };
+
+                frame.fill_text(Text {
+                    content: label,
+                    position: Point::new(bottom.x, marker_y),
+                    color,
+                    size: Pixels(label_size),
+                    align_x: alignment::Horizontal::Center.into(),
+                    align_y: alignment::Vertical::Bottom,
+                    ..Default::default()
+                });
+
+                marker_y -= label_size * 1.2;
+            }
+
+            if self.middle_c_marker && key.key == MIDDLE_C {
+                let radius = natural_width * 0.2;
+                let marker = Path::circle(Point::new(bottom.x, marker_y - radius), radius);
+                frame.fill(&marker, MARKER_COLOR);
+            }
+        }
+
+        if state.touch {
+            for control in Control::ALL {
+                let top_left = control.position();
+
+                frame.fill_rectangle(
+                    top_left,
+                    Size::new(CONTROL_SIZE, CONTROL_SIZE),
+                    MARKER_COLOR,
+                );
+                frame.fill_text(Text {
+                    content: control.label().to_owned(),
+                    position: top_left + Vector::new(CONTROL_SIZE / 2., CONTROL_SIZE / 2.),
+                    color: Color::WHITE,
+                    size: Pixels(CONTROL_SIZE * 0.6),
+                    align_x: alignment::Horizontal::Center.into(),
+                    align_y: alignment::Vertical::Center,
+                    ..Default::default()
+                });
+            }
+        }
+
+        // Pressed pedals are listed in the top right corner.
+        let height = PEDAL_LABEL_SIZE * 1.8;
+
+        for (idx, pedal) in self.pedals.iter().enumerate() {
+            let top_left = Point::new(
+                frame.width() - PEDAL_LABEL_WIDTH - padding,
+                padding + idx as f32 * (height + padding),
+            );
+
+            frame.fill_rectangle(top_left, Size::new(PEDAL_LABEL_WIDTH, height), MARKER_COLOR);
+            frame.fill_text(Text {
+                content: pedal.to_string(),
+                position: top_left + Vector::new(PEDAL_LABEL_WIDTH / 2., height / 2.),
+                color: Color::WHITE,
+                size: Pixels(PEDAL_LABEL_SIZE),
+                align_x: alignment::Horizontal::Center.into(),
+                align_y: alignment::Vertical::Center,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Returns the key at the point along with the velocity, which increases
+    /// towards the bottom of the key.
+    fn find_key(&self, pt: Point) -> Option<(Key, u7)> {
         let cmp = |key: &KeyData| {
             if key.offset.x < pt.x {
                 if key.offset.x + key.size.width > pt.x {
@@ -165,22 +637,34 @@ impl Piano {
             }
         };
 
-        if pt.y < SHARP_KEY_HEIGHT {
-            let key = self
-                .sharp_keys
-                .binary_search_by(cmp)
-                .map(|idx| self.sharp_keys[idx].key)
-                .ok();
-
-            if key.is_some() {
-                return key;
-            }
-        }
+        let data = (pt.y < SHARP_KEY_HEIGHT)
+            .then(|| {
+                self.sharp_keys
+                    .binary_search_by(cmp)
+                    .map(|idx| &self.sharp_keys[idx])
+                    .ok()
+            })
+            .flatten()
+            .or_else(|| {
+                self.natural_keys
+                    .binary_search_by(cmp)
+                    .map(|idx| &self.natural_keys[idx])
+                    .ok()
+            })?;
+
+        let depth = ((pt.y - data.offset.y) / data.size.height).clamp(0., 1.);
+        let vel = MIN_VELOCITY + (depth * (MAX_VELOCITY - MIN_VELOCITY) as f32).round() as u8;
+
+        Some((data.key, vel.into()))
+    }
 
-        self.natural_keys
-            .binary_search_by(cmp)
-            .map(|idx| self.natural_keys[idx].key)
-            .ok()
+    /// Returns the key under the point in window coordinates, e.g. touch
+    /// positions.
+    fn find_key_abs(&self, state: &State, bounds: Rectangle, pt: Point) -> Option<(Key, u7)> {
+        bounds
+            .contains(pt)
+            .then(|| Point::new(pt.x - bounds.x, pt.y - bounds.y))
+            .and_then(|pt| self.find_key(state.translate(pt)))
     }
 }
 
@@ -194,39 +678,92 @@ impl canvas::Program<Message> for Piano {
         bounds: iced::Rectangle,
         cursor: mouse::Cursor,
     ) -> Option<canvas::Action<Message>> {
-        match event {
-            iced::Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)) => {
-                if let Some(cur_pos) = cursor.position_in(bounds) {
-                    if let Some(key) = self.find_key(state.translate(cur_pos)) {
-                        state.pressed_key = Some(key);
-
-                        let msg = Message::InputEvent(midly::MidiMessage::NoteOn {
-                            key: key.to_midi(),
-                            vel: 1.into(),
-                        });
-
-                        return Some(canvas::Action::publish(msg).and_capture());
+        let action = match event {
+            iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let target = cursor
+                    .position_in(bounds)
+                    .and_then(|pos| self.find_key(state.translate(pos)));
+
+                state.mouse_down = target.is_some();
+                self.move_pointer(state, Pointer::Mouse, target)
+            }
+
+            // Dragging across keys releases the previous key and presses the new one.
+            iced::Event::Mouse(mouse::Event::CursorMoved { .. }) if state.mouse_down => {
+                let target = cursor
+                    .position_in(bounds)
+                    .and_then(|pos| self.find_key(state.translate(pos)));
+
+                self.move_pointer(state, Pointer::Mouse, target)
+            }
+
+            iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                state.mouse_down = false;
+                self.move_pointer(state, Pointer::Mouse, None)
+            }
+
+            iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = *modifiers;
+                None
+            }
+
+            // Scrolling pans the keyboard, scrolling with the command key held zooms it.
+            iced::Event::Mouse(mouse::Event::WheelScrolled { delta }) if cursor.is_over(bounds) => {
+                let pos = cursor.position_in(bounds).unwrap_or(Point::ORIGIN);
+
+                let (x, y) = match *delta {
+                    mouse::ScrollDelta::Lines { x, y } => (x, y),
+                    mouse::ScrollDelta::Pixels { x, y } => {
+                        (x / state.key_width.max(1.), y / state.key_width.max(1.))
                     }
+                };
+
+                if state.modifiers.command() {
+                    state.zoom(self, 1. - y.clamp(-1., 1.) * ZOOM_STEP, pos.x);
+                } else {
+                    state.pan(self, if x != 0. { -x } else { -y });
                 }
+
+                Some(canvas::Action::request_redraw().and_capture())
             }
 
-            iced::Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) => {
-                if let Some(key) = state.pressed_key.take() {
-                    let msg = Message::InputEvent(midly::MidiMessage::NoteOff {
-                        key: key.to_midi(),
-                        vel: 0.into(),
-                    });
+            // Touch positions are in window coordinates, unlike the cursor.
+            iced::Event::Touch(touch::Event::FingerPressed { id, position }) => {
+                if let Some(control) = state.find_control(*position) {
+                    state.apply(self, control);
+                    Some(canvas::Action::request_redraw().and_capture())
+                } else {
+                    // The controls are shown once the keyboard is touched.
+                    let touched = bounds.contains(*position) && !state.touch;
+                    state.touch |= touched;
+                    let target = self.find_key_abs(state, bounds, *position);
 
-                    return Some(canvas::Action::publish(msg).and_capture());
+                    self.move_pointer(state, Pointer::Finger(*id), target)
+                        .or_else(|| touched.then(canvas::Action::request_redraw))
                 }
             }
 
-            _ => {}
+            iced::Event::Touch(touch::Event::FingerMoved { id, position })
+                if state.held.contains_key(&Pointer::Finger(*id)) =>
+            {
+                let target = self.find_key_abs(state, bounds, *position);
+                self.move_pointer(state, Pointer::Finger(*id), target)
+            }
+
+            iced::Event::Touch(
+                touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. },
+            ) => self.move_pointer(state, Pointer::Finger(*id), None),
+
+            _ => None,
         };
 
+        if action.is_some() {
+            return action;
+        }
+
         if state.bounds != bounds {
             state.bounds = bounds;
-            state.update_translation(&self.keyboard);
+            state.update_translation(self);
             Some(canvas::Action::request_redraw())
         } else {
             None
@@ -237,13 +774,20 @@ impl canvas::Program<Message> for Piano {
         &self,
         state: &Self::State,
         renderer: &Renderer,
-        _theme: &Theme,
+        theme: &Theme,
         bounds: iced::Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry<Renderer>> {
         let natural_stroke = Stroke::default().with_width(1.);
-        let sharp_fill = Fill::from(Color::BLACK);
-        let pressed_fill = Fill::from(Color::from_rgb(0.7, 0.7, 0.7));
+        // Dim the keys in dark themes.
+        let (natural_fill, sharp_fill) = if theme.extended_palette().is_dark {
+            (
+                Color::from_rgb(0.85, 0.85, 0.85),
+                Fill::from(Color::from_rgb(0.1, 0.1, 0.1)),
+            )
+        } else {
+            (Color::WHITE, Fill::from(Color::BLACK))
+        };
 
         let mut frame = Frame::new(renderer, bounds.size());
 
@@ -252,27 +796,32 @@ impl canvas::Program<Message> for Piano {
             // strokes being outside of the actual shape bounds.
             frame.translate(state.translation - Vector::new(1., 1.));
             frame.scale_nonuniform(state.scale);
-            frame.fill_rectangle(Point::ORIGIN, Size::new(1., 1.), Color::WHITE);
+            frame.fill_rectangle(Point::ORIGIN, Size::new(1., 1.), natural_fill);
 
             for key in &self.natural_keys {
-                if self.is_pressed(&key.key) {
-                    frame.fill_rectangle(key.offset, key.size, pressed_fill);
+                if let Some(color) = self.key_color(&key.key, true) {
+                    frame.fill_rectangle(key.offset, key.size, color);
                 }
 
                 frame.stroke_rectangle(key.offset, key.size, natural_stroke);
             }
         });
 
-        frame.translate(state.translation);
-        frame.scale_nonuniform(state.scale);
+        frame.with_save(|frame| {
+            frame.translate(state.translation);
+            frame.scale_nonuniform(state.scale);
 
-        for key in &self.sharp_keys {
-            if self.is_pressed(&key.key) {
-                frame.fill_rectangle(key.offset, key.size, pressed_fill);
-            } else {
-                frame.fill_rectangle(key.offset, key.size, sharp_fill);
+            for key in &self.sharp_keys {
+                match self.key_color(&key.key, false) {
+                    Some(color) => frame.fill_rectangle(key.offset, key.size, color),
+                    None => frame.fill_rectangle(key.offset, key.size, sharp_fill),
+                }
             }
-        }
+        });
+
+        // Overlays are drawn unscaled, so that text and markers keep their
+        // proportions.
+        self.draw_overlays(state, &mut frame);
 
         vec![frame.into_geometry()]
     }
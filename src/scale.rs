@@ -0,0 +1,383 @@
+use {
+    crate::keyboard::{Interval, Key, KeySignature, Keyboard, Pitch, PitchClass},
+    derive_more::Display,
+    serde::{Deserialize, Serialize},
+};
+
+#[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScaleKind {
+    #[display("major")]
+    Major,
+
+    #[display("natural minor")]
+    NaturalMinor,
+
+    #[display("harmonic minor")]
+    HarmonicMinor,
+
+    #[display("melodic minor")]
+    MelodicMinor,
+
+    #[display("dorian")]
+    Dorian,
+
+    #[display("phrygian")]
+    Phrygian,
+
+    #[display("lydian")]
+    Lydian,
+
+    #[display("mixolydian")]
+    Mixolydian,
+
+    #[display("locrian")]
+    Locrian,
+
+    #[display("major pentatonic")]
+    MajorPentatonic,
+
+    #[display("minor pentatonic")]
+    MinorPentatonic,
+
+    #[display("chromatic")]
+    Chromatic,
+}
+
+impl ScaleKind {
+    pub const ALL: [Self; 12] = [
+        Self::Major,
+        Self::NaturalMinor,
+        Self::HarmonicMinor,
+        Self::MelodicMinor,
+        Self::Dorian,
+        Self::Phrygian,
+        Self::Lydian,
+        Self::Mixolydian,
+        Self::Locrian,
+        Self::MajorPentatonic,
+        Self::MinorPentatonic,
+        Self::Chromatic,
+    ];
+
+    /// Intervals of the scale degrees above the tonic, including the tonic
+    /// itself. Melodic minor is ascending.
+    pub fn intervals(&self) -> &'static [Interval] {
+        use Interval as I;
+
+        match self {
+            Self::Major => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+
+            Self::NaturalMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::HarmonicMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+
+            Self::MelodicMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+
+            Self::Dorian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::Phrygian => &[
+                I::UNISON,
+                I::MINOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::Lydian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::AUGMENTED_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+
+            Self::Mixolydian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::Locrian => &[
+                I::UNISON,
+                I::MINOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::DIMINISHED_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::MajorPentatonic => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+            ],
+
+            Self::MinorPentatonic => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+
+            Self::Chromatic => &[
+                I::UNISON,
+                I::AUGMENTED_UNISON,
+                I::MAJOR_SECOND,
+                I::AUGMENTED_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::AUGMENTED_FOURTH,
+                I::PERFECT_FIFTH,
+                I::AUGMENTED_FIFTH,
+                I::MAJOR_SIXTH,
+                I::AUGMENTED_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+        }
+    }
+
+    /// Interval from the tonic to the tonic of the major scale sharing the key
+    /// signature.
+    fn relative_major(&self) -> Interval {
+        match self {
+            Self::Major | Self::MajorPentatonic | Self::Chromatic => Interval::UNISON,
+
+            Self::NaturalMinor
+            | Self::HarmonicMinor
+            | Self::MelodicMinor
+            | Self::MinorPentatonic => Interval::MINOR_THIRD,
+
+            Self::Dorian => Interval::MINOR_SEVENTH,
+            Self::Phrygian => Interval::MINOR_SIXTH,
+            Self::Lydian => Interval::PERFECT_FIFTH,
+            Self::Mixolydian => Interval::PERFECT_FOURTH,
+            Self::Locrian => Interval::MINOR_SECOND,
+        }
+    }
+}
+
+#[derive(Display, Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[display("{} {}", tonic, kind)]
+pub struct Scale {
+    pub tonic: PitchClass,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    pub const fn new(tonic: PitchClass, kind: ScaleKind) -> Self {
+        Self { tonic, kind }
+    }
+
+    /// Returns the spelled scale degrees, starting with the tonic. Degrees that
+    /// can't be spelled with at most a double accidental are skipped.
+    pub fn degrees(&self) -> impl Iterator<Item = PitchClass> + use<> {
+        let tonic = self.tonic.oct(4);
+
+        self.kind
+            .intervals()
+            .iter()
+            .filter_map(move |interval| tonic.transpose(*interval).ok())
+            .map(|pitch| pitch.class())
+    }
+
+    /// Returns spelled pitches of the scale playable on the keyboard, in
+    /// ascending order.
+    pub fn pitches(&self, keyboard: &Keyboard) -> Vec<Pitch> {
+        let octaves = keyboard.first().oct.saturating_sub(1)..=keyboard.last().oct;
+
+        let mut pitches = octaves
+            .flat_map(|oct| {
+                let tonic = self.tonic.oct(oct);
+
+                self.kind
+                    .intervals()
+                    .iter()
+                    .filter_map(move |interval| tonic.transpose(*interval).ok())
+            })
+            .filter_map(|pitch| Some((pitch.to_key().ok()?, pitch)))
+            .filter(|(key, _)| keyboard.contains(key))
+            .collect::<Vec<_>>();
+
+        pitches.sort_by_key(|(key, _)| *key);
+        pitches.into_iter().map(|(_, pitch)| pitch).collect()
+    }
+
+    /// Returns keys of the scale playable on the keyboard, in ascending order.
+    pub fn keys(&self, keyboard: &Keyboard) -> Vec<Key> {
+        self.pitches(keyboard)
+            .iter()
+            .filter_map(|pitch| pitch.to_key().ok())
+            .collect()
+    }
+
+    /// Returns the spelling of the key within the scale, or `None` if the key
+    /// doesn't belong to the scale.
+    pub fn spell(&self, key: Key) -> Option<Pitch> {
+        let octaves = key.oct.saturating_sub(1)..=key.oct.saturating_add(1);
+
+        self.degrees().find_map(|degree| {
+            octaves
+                .clone()
+                .map(|oct| degree.oct(oct))
+                .find(|pitch| pitch.to_key().is_ok_and(|pitch_key| pitch_key == key))
+        })
+    }
+
+    pub fn contains(&self, key: Key) -> bool {
+        self.spell(key).is_some()
+    }
+
+    /// Returns the conventional key signature for the scale, or `None` if it
+    /// would require more than 7 sharps or flats.
+    pub fn key_signature(&self) -> Option<KeySignature> {
+        self.tonic
+            .oct(4)
+            .transpose(self.kind.relative_major())
+            .ok()
+            .and_then(|tonic| KeySignature::from_tonic(tonic.class()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::keyboard::{Accidental, KeyPos, KeyboardSize, Letter},
+        wasm_bindgen_test::*,
+    };
+
+    const C: PitchClass = PitchClass::new(Letter::C, Accidental::Natural);
+    const D: PitchClass = PitchClass::new(Letter::D, Accidental::Natural);
+    const E: PitchClass = PitchClass::new(Letter::E, Accidental::Natural);
+    const G: PitchClass = PitchClass::new(Letter::G, Accidental::Natural);
+    const A: PitchClass = PitchClass::new(Letter::A, Accidental::Natural);
+    const B_FLAT: PitchClass = PitchClass::new(Letter::B, Accidental::Flat);
+
+    #[wasm_bindgen_test]
+    fn degrees() {
+        let names = |scale: Scale| {
+            scale
+                .degrees()
+                .map(|degree| format!("{}{}", degree.letter.as_str(), degree.accidental.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(Scale::new(G, ScaleKind::Major)), [
+            "G", "A", "B", "C", "D", "E", "F#"
+        ]);
+        assert_eq!(names(Scale::new(D, ScaleKind::Dorian)), [
+            "D", "E", "F", "G", "A", "B", "C"
+        ]);
+        assert_eq!(names(Scale::new(A, ScaleKind::HarmonicMinor)), [
+            "A", "B", "C", "D", "E", "F", "G#"
+        ]);
+        assert_eq!(names(Scale::new(B_FLAT, ScaleKind::MinorPentatonic)), [
+            "Bb", "Db", "Eb", "F", "Ab"
+        ]);
+        assert_eq!(Scale::new(E, ScaleKind::Chromatic).degrees().count(), 12);
+    }
+
+    #[wasm_bindgen_test]
+    fn keys() {
+        let kbd = Keyboard::standard_88_key();
+
+        let c_major = Scale::new(C, ScaleKind::Major).keys(&kbd);
+        assert_eq!(c_major.len(), kbd.num_natural_keys());
+        assert!(c_major.iter().all(Key::is_natural));
+        assert!(c_major.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let chromatic = Scale::new(C, ScaleKind::Chromatic).keys(&kbd);
+        assert_eq!(chromatic.len(), kbd.num_keys());
+
+        let kbd = KeyboardSize::Keys25.to_keyboard();
+        let g_major = Scale::new(G, ScaleKind::Major);
+        assert_eq!(g_major.keys(&kbd).len(), 15);
+        assert!(g_major.contains(KeyPos::FSharp.oct(4)));
+        assert!(!g_major.contains(KeyPos::F.oct(4)));
+        assert_eq!(
+            g_major.spell(KeyPos::FSharp.oct(4)),
+            Some(Pitch::new(Letter::F, Accidental::Sharp, 4))
+        );
+
+        let c_flat_major = Scale::new(
+            PitchClass::new(Letter::C, Accidental::Flat),
+            ScaleKind::Major,
+        );
+        assert_eq!(
+            c_flat_major.spell(KeyPos::B.oct(3)),
+            Some(Pitch::new(Letter::C, Accidental::Flat, 4))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn key_signatures() {
+        let sig = |scale: Scale| scale.key_signature().map(|sig| sig.fifths());
+
+        assert_eq!(sig(Scale::new(C, ScaleKind::Major)), Some(0));
+        assert_eq!(sig(Scale::new(D, ScaleKind::Dorian)), Some(0));
+        assert_eq!(sig(Scale::new(E, ScaleKind::Phrygian)), Some(0));
+        assert_eq!(sig(Scale::new(A, ScaleKind::MelodicMinor)), Some(0));
+        assert_eq!(sig(Scale::new(G, ScaleKind::Mixolydian)), Some(0));
+        assert_eq!(sig(Scale::new(B_FLAT, ScaleKind::Major)), Some(-2));
+        assert_eq!(sig(Scale::new(G, ScaleKind::NaturalMinor)), Some(-2));
+        assert_eq!(
+            sig(Scale::new(
+                PitchClass::new(Letter::G, Accidental::Sharp),
+                ScaleKind::Major
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use {midly::num::u7, std::f32::consts::TAU};
+
+/// Duration of the rendered notes, in seconds. Notes held longer than that fade
+/// out, like on an acoustic piano.
+pub const NOTE_DURATION: f32 = 2.5;
+/// Time constant of the fade out when a note is released, in seconds.
+pub const RELEASE: f64 = 0.05;
+const ATTACK: f32 = 0.005;
+/// Gain of the quietest note, so that notes played with a low velocity remain
+/// audible.
+const MIN_GAIN: f32 = 0.3;
+/// Relative amplitude and decay rate of the harmonics of the piano tone. Higher
+/// harmonics are quieter and fade out faster.
+const HARMONICS: [(f32, f32); 6] = [
+    (1.0, 1.2),
+    (0.5, 1.8),
+    (0.3, 2.6),
+    (0.15, 3.4),
+    (0.1, 4.2),
+    (0.05, 5.0),
+];
+
+/// Short sounds giving feedback on the played keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    Click,
+    Error,
+}
+
+/// Returns the frequency of the key in equal temperament, with A4 at 440 Hz.
+pub fn frequency(key: u7) -> f32 {
+    440. * 2f32.powf((key.as_int() as f32 - 69.) / 12.)
+}
+
+/// Returns the gain of a note played with the velocity.
+pub fn velocity_gain(vel: u7) -> f32 {
+    let vel = vel.as_int() as f32 / 127.;
+    MIN_GAIN + (1. - MIN_GAIN) * vel * vel
+}
+
+/// Renders the piano tone of the key as additive synthesis of its harmonics.
+pub fn render_note(key: u7, sample_rate: f32) -> Vec<f32> {
+    let freq = frequency(key);
+    // Higher notes fade out faster.
+    let decay_scale = (freq / 440.).sqrt();
+    let norm = HARMONICS.iter().map(|(amp, _)| amp).sum::<f32>();
+
+    // Harmonics above the Nyquist frequency would alias.
+    let harmonics = HARMONICS
+        .iter()
+        .enumerate()
+        .map(|(idx, (amp, decay))| (freq * (idx + 1) as f32, *amp, decay * decay_scale))
+        .filter(|(freq, ..)| *freq < sample_rate / 2.)
+        .collect::<Vec<_>>();
+
+    render(NOTE_DURATION, sample_rate, |time| {
+        let sample = harmonics
+            .iter()
+            .map(|(freq, amp, decay)| amp * (-decay * time).exp() * (TAU * freq * time).sin())
+            .sum::<f32>();
+
+        (time / ATTACK).min(1.) * sample / norm
+    })
+}
+
+pub fn render_effect(effect: Effect, sample_rate: f32) -> Vec<f32> {
+    match effect {
+        // A short high pitched tick.
+        Effect::Click => render(0.03, sample_rate, |time| {
+            0.5 * (-150. * time).exp() * (TAU * 1760. * time).sin()
+        }),
+
+        // Two low tones a semitone apart, beating against each other.
+        Effect::Error => render(0.25, sample_rate, |time| {
+            let envelope = (time / ATTACK).min(1.) * (-8. * time).exp();
+            let tone = (TAU * 110. * time).sin() + (TAU * 116.54 * time).sin();
+            0.4 * envelope * tone
+        }),
+    }
+}
+
+fn render(duration: f32, sample_rate: f32, sample: impl Fn(f32) -> f32) -> Vec<f32> {
+    let len = (duration * sample_rate) as usize;
+
+    (0..len)
+        .map(|idx| sample(idx as f32 / sample_rate))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, wasm_bindgen_test::*};
+
+    const SAMPLE_RATE: f32 = 44100.;
+
+    /// Magnitude of the frequency component in the samples.
+    fn magnitude(samples: &[f32], freq: f32) -> f32 {
+        let (re, im) = samples
+            .iter()
+            .enumerate()
+            .fold((0., 0.), |(re, im), (idx, sample)| {
+                let phase = TAU * freq * idx as f32 / SAMPLE_RATE;
+                (re + sample * phase.cos(), im + sample * phase.sin())
+            });
+
+        (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[wasm_bindgen_test]
+    fn frequencies() {
+        assert_eq!(frequency(69.into()), 440.);
+        assert_eq!(frequency(81.into()), 880.);
+        assert!((frequency(60.into()) - 261.63).abs() < 0.01);
+        assert!((frequency(21.into()) - 27.5).abs() < 0.01);
+    }
+
+    #[wasm_bindgen_test]
+    fn velocity() {
+        assert_eq!(velocity_gain(127.into()), 1.);
+        assert_eq!(velocity_gain(0.into()), MIN_GAIN);
+        assert!(velocity_gain(64.into()) < velocity_gain(100.into()));
+    }
+
+    #[wasm_bindgen_test]
+    fn note() {
+        let samples = render_note(69.into(), SAMPLE_RATE);
+
+        assert_eq!(samples.len(), (NOTE_DURATION * SAMPLE_RATE) as usize);
+        assert_eq!(samples[0], 0.);
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.));
+
+        // The fundamental and its harmonics are the loudest components.
+        let window = &samples[..4410];
+        assert!(magnitude(window, 440.) > 10. * magnitude(window, 660.));
+        assert!(magnitude(window, 880.) > 10. * magnitude(window, 1100.));
+        assert!(magnitude(window, 440.) > magnitude(window, 880.));
+
+        // The note fades out.
+        let tail = &samples[samples.len() - 4410..];
+        assert!(rms(tail) < rms(window) / 10.);
+    }
+
+    #[wasm_bindgen_test]
+    fn high_notes() {
+        // Harmonics of C8 above the Nyquist frequency are omitted.
+        let samples = render_note(108.into(), SAMPLE_RATE);
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.));
+        assert!(magnitude(&samples[..4410], frequency(108.into())) > 0.1);
+    }
+
+    #[wasm_bindgen_test]
+    fn effects() {
+        for effect in [Effect::Click, Effect::Error] {
+            let samples = render_effect(effect, SAMPLE_RATE);
+
+            assert!(!samples.is_empty());
+            assert!(samples.len() < SAMPLE_RATE as usize);
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.));
+            assert!(rms(&samples) > 0.01);
+        }
+    }
+}
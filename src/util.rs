@@ -1,4 +1,25 @@
-use {wasm_bindgen::JsValue, wasm_bindgen_futures::JsFuture};
+use {
+    iced::futures::channel::oneshot,
+    std::fmt,
+    tap::TapFallible as _,
+    wasm_bindgen::{JsCast as _, JsValue, closure::Closure},
+    wasm_bindgen_futures::JsFuture,
+    web_sys::{Blob, BlobPropertyBag, Document, HtmlAnchorElement, HtmlInputElement, Url},
+};
+
+/// Time after which the url of a downloaded file is released, in milliseconds.
+const DOWNLOAD_REVOKE_DELAY: i32 = 10_000;
+
+/// Error with a suggestion on how to resolve it, shown to the user.
+pub trait Hint: fmt::Display {
+    /// Returns a suggestion on how to resolve the error.
+    fn hint(&self) -> &'static str;
+
+    /// Returns the error message followed by the hint.
+    fn describe(&self) -> String {
+        format!("{self}. {}", self.hint())
+    }
+}
 
 pub async fn sleep(delay: i32) {
     let mut cb = |resolve: js_sys::Function, _: js_sys::Function| {
@@ -24,13 +45,44 @@ pub enum Error {
 
     #[error("Failed to enter fullscreen: {0}")]
     Fullscreen(String),
+
+    #[error("Failed to reload the page: {0}")]
+    Reload(String),
+
+    #[error("Failed to open the file: {0}")]
+    OpenFile(String),
+
+    #[error("Failed to download the file: {0}")]
+    Download(String),
+
+    #[error("Failed to listen to page events: {0}")]
+    Listen(String),
 }
 
-pub fn toggle_fullscreen() -> Result<(), Error> {
-    let document = web_sys::window()
+impl Hint for Error {
+    fn hint(&self) -> &'static str {
+        match self {
+            Self::Fullscreen(_) => "Your browser may not support fullscreen mode.",
+            Self::Reload(_) => "Reload the page manually.",
+            Self::OpenFile(_) => "Make sure the file is readable and try again.",
+            Self::Download(_) => "Make sure the browser is allowed to download files.",
+            Self::NoWindow | Self::NoDocument | Self::NoBody | Self::Listen(_) => {
+                "Reload the page and try again."
+            }
+        }
+    }
+}
+
+pub fn reload() -> Result<(), Error> {
+    web_sys::window()
         .ok_or(Error::NoWindow)?
-        .document()
-        .ok_or(Error::NoDocument)?;
+        .location()
+        .reload()
+        .map_err(|err| Error::Reload(js_error_to_string(err)))
+}
+
+pub fn toggle_fullscreen() -> Result<(), Error> {
+    let document = document()?;
 
     if document.fullscreen_element().is_some() {
         document.exit_fullscreen();
@@ -44,6 +96,120 @@ pub fn toggle_fullscreen() -> Result<(), Error> {
     }
 }
 
-fn js_error_to_string(err: JsValue) -> String {
+/// Shows the file dialog, returning the name and the contents of the chosen
+/// file, or `None` if the dialog is cancelled.
+pub async fn open_file(accept: &str) -> Result<Option<(String, Vec<u8>)>, Error> {
+    let input = document()?
+        .create_element("input")
+        .map_err(|err| Error::OpenFile(js_error_to_string(err)))?
+        .unchecked_into::<HtmlInputElement>();
+
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let (tx, rx) = oneshot::channel();
+    let mut tx = Some(tx);
+
+    // Fired once either a file is chosen or the dialog is cancelled.
+    let on_close = Closure::<dyn FnMut()>::new(move || {
+        if let Some(tx) = tx.take() {
+            let _ = tx.send(());
+        }
+    });
+
+    for event in ["change", "cancel"] {
+        input
+            .add_event_listener_with_callback(event, on_close.as_ref().unchecked_ref())
+            .map_err(|err| Error::OpenFile(js_error_to_string(err)))?;
+    }
+
+    input.click();
+
+    let _ = rx.await;
+    drop(on_close);
+
+    let Some(file) = input.files().and_then(|files| files.get(0)) else {
+        return Ok(None);
+    };
+
+    let data = JsFuture::from(file.array_buffer())
+        .await
+        .map_err(|err| Error::OpenFile(js_error_to_string(err)))?;
+
+    Ok(Some((file.name(), js_sys::Uint8Array::new(&data).to_vec())))
+}
+
+/// Saves the data as a file in the downloads of the browser.
+pub fn download(file_name: &str, mime_type: &str, data: &[u8]) -> Result<(), Error> {
+    let to_error = |err| Error::Download(js_error_to_string(err));
+
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(to_error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(to_error)?;
+
+    let link = document()?
+        .create_element("a")
+        .map_err(to_error)?
+        .unchecked_into::<HtmlAnchorElement>();
+
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+
+    // The download starts asynchronously, so the url has to stay valid for a
+    // while after the click.
+    wasm_bindgen_futures::spawn_local(async move {
+        sleep(DOWNLOAD_REVOKE_DELAY).await;
+
+        let _ = Url::revoke_object_url(&url)
+            .tap_err(|err| tracing::warn!(?err, "failed to revoke the download url"));
+    });
+
+    Ok(())
+}
+
+/// Listener of a window event, which is removed once dropped.
+pub struct EventListener {
+    event: &'static str,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            let _ = window.remove_event_listener_with_callback(
+                self.event,
+                self.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// Calls the callback once the page is hidden to be unloaded, e.g. when the tab
+/// is closed, reloaded or navigated away from. Unlike `beforeunload`, the
+/// `pagehide` event is fired by mobile browsers as well.
+pub fn on_page_hide(callback: impl FnMut() + 'static) -> Result<EventListener, Error> {
+    let event = "pagehide";
+    let callback = Closure::<dyn FnMut()>::new(callback);
+
+    web_sys::window()
+        .ok_or(Error::NoWindow)?
+        .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+        .map_err(|err| Error::Listen(js_error_to_string(err)))?;
+
+    Ok(EventListener { event, callback })
+}
+
+fn document() -> Result<Document, Error> {
+    web_sys::window()
+        .ok_or(Error::NoWindow)?
+        .document()
+        .ok_or(Error::NoDocument)
+}
+
+pub fn js_error_to_string(err: JsValue) -> String {
     err.as_string().unwrap_or_else(|| "<no data>".to_owned())
 }
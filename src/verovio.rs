@@ -1,36 +1,72 @@
-use {wasm_bindgen::prelude::*, wasm_bindgen_futures::js_sys::JsString};
+use {
+    crate::util::{Hint, js_error_to_string},
+    wasm_bindgen::prelude::*,
+    wasm_bindgen_futures::js_sys::JsString,
+};
 
 #[wasm_bindgen]
 extern "C" {
     type Verovio;
 
-    #[wasm_bindgen(static_method_of = Verovio)]
-    async fn init();
+    #[wasm_bindgen(static_method_of = Verovio, catch)]
+    async fn init() -> Result<(), JsValue>;
 
-    #[wasm_bindgen(static_method_of = Verovio)]
-    async fn ping();
+    #[wasm_bindgen(static_method_of = Verovio, catch)]
+    async fn ping() -> Result<(), JsValue>;
 
     #[wasm_bindgen(static_method_of = Verovio, js_name = getOptions)]
     async fn get_options() -> JsString;
 
-    #[wasm_bindgen(static_method_of = Verovio, js_name = setOptions)]
-    async fn set_options(opts: JsString);
+    #[wasm_bindgen(static_method_of = Verovio, js_name = setOptions, catch)]
+    async fn set_options(opts: JsString) -> Result<(), JsValue>;
 
     #[wasm_bindgen(static_method_of = Verovio, js_name = resetOptions)]
     async fn reset_options();
 
-    #[wasm_bindgen(static_method_of = Verovio, js_name = convertToSVG)]
-    async fn convert_to_svg(mei: JsString) -> JsString;
+    #[wasm_bindgen(static_method_of = Verovio, js_name = convertToSVG, catch)]
+    async fn convert_to_svg(mei: JsString) -> Result<JsString, JsValue>;
 }
 
-pub async fn initialize() {
+#[derive(Debug, thiserror::Error, Clone)]
+pub enum Error {
+    #[error("Failed to load the music notation engine: {0}")]
+    InitFailed(String),
+
+    #[error("Failed to render the music sheet: {0}")]
+    RenderFailed(String),
+}
+
+impl Hint for Error {
+    fn hint(&self) -> &'static str {
+        match self {
+            Self::InitFailed(_) => {
+                "Check your internet connection and reload the page, or try a different browser."
+            }
+            Self::RenderFailed(_) => {
+                "Skip the challenge, or reload the page if the problem persists."
+            }
+        }
+    }
+}
+
+pub async fn initialize() -> Result<(), Error> {
     tracing::info!("initializing verovio...");
-    Verovio::init().await;
-    Verovio::ping().await;
-    Verovio::set_options(include_str!("../resources/verovio_options.json").into()).await;
+    Verovio::init().await.map_err(init_error)?;
+    Verovio::ping().await.map_err(init_error)?;
+    Verovio::set_options(include_str!("../resources/verovio_options.json").into())
+        .await
+        .map_err(init_error)?;
     tracing::info!("ready");
+    Ok(())
+}
+
+pub async fn convert_to_svg(mei: String) -> Result<String, Error> {
+    Verovio::convert_to_svg(mei.into())
+        .await
+        .map(Into::into)
+        .map_err(|err| Error::RenderFailed(js_error_to_string(err)))
 }
 
-pub async fn convert_to_svg(mei: String) -> String {
-    Verovio::convert_to_svg(mei.into()).await.into()
+fn init_error(err: JsValue) -> Error {
+    Error::InitFailed(js_error_to_string(err))
 }
@@ -4,7 +4,12 @@ const worker = new Worker("./web/verovio-worker.mjs", {
   type: "module",
 });
 
-const initPromise = new Promise((resolve) => {
+const initPromise = new Promise((resolve, reject) => {
+  worker.onerror = function (event) {
+    console.error("failed to load worker", event);
+    reject(event.message ?? "failed to load worker");
+  };
+
   worker.onmessage = function ({ data }) {
     if (data !== "ready") {
       console.error("invalid init message", data);
@@ -12,6 +17,7 @@ const initPromise = new Promise((resolve) => {
     }
 
     worker.onmessage = null;
+    worker.onerror = null;
     console.log("worker initialized");
     resolve();
   };